@@ -1,39 +1,209 @@
-use anyhow::Error;
-use std::collections::HashMap;
+pub mod utils;
+
+use anyhow::{anyhow, Error};
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
 use std::fs::read_to_string;
 use std::iter::zip;
 use std::path::Path;
+use utils::counter::Counter;
+use utils::rand::{maybe_generate, Rng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Score {
+    Integer(i64),
+    Real(f64),
+    // E.g. a correlation with a constant column.
+    Undefined,
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Integer(n) => write!(f, "{}", n),
+            Score::Real(x) => write!(f, "{}", x),
+            Score::Undefined => write!(f, "undefined"),
+        }
+    }
+}
+
+type Metric = fn(&[i32], &[i32]) -> Score;
 
-fn get_col(input: &[Vec<i32>], i: usize) -> Vec<i32> {
-    let mut col: Vec<_> = input.iter().map(|r| r[i]).collect();
+const METRICS: [(&str, Metric); 4] = [
+    ("distance", |a, b| Score::Integer(distance(a, b))),
+    ("similarity", |a, b| Score::Integer(similarity(a, b))),
+    ("squared distance", |a, b| {
+        Score::Integer(squared_distance(a, b))
+    }),
+    ("rank correlation", |a, b| {
+        rank_correlation(a, b).map_or(Score::Undefined, Score::Real)
+    }),
+];
+
+fn sorted(col: &[i32]) -> Vec<i32> {
+    let mut col = col.to_vec();
     col.sort();
     col
 }
 
-fn solve1(col1: &[i32], col2: &[i32]) -> i32 {
-    zip(col1, col2).map(|(x, y)| (x - y).abs()).sum()
+// Reads whitespace separated columns; every row must have as many values as the first one.
+fn parse_columns(input: &str) -> Result<Vec<Vec<i32>>, Error> {
+    let mut columns: Vec<Vec<i32>> = Vec::new();
+    for (row, line) in input.lines().enumerate() {
+        let values = line
+            .split_whitespace()
+            .map(|value| value.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("row {}: {}", row + 1, e))?;
+        if row == 0 {
+            columns = vec![Vec::new(); values.len()];
+        }
+        if values.len() != columns.len() {
+            return Err(anyhow!(
+                "row {}: expected {} columns, found {}",
+                row + 1,
+                columns.len(),
+                values.len()
+            ));
+        }
+        for (column, value) in columns.iter_mut().zip(values) {
+            column.push(value);
+        }
+    }
+    Ok(columns)
 }
 
-fn solve2(col1: &[i32], col2: &[i32]) -> i32 {
-    let counts = col2.iter().fold(HashMap::new(), |mut acc, x| {
-        acc.entry(x).and_modify(|c| *c += 1).or_insert(1);
-        acc
-    });
-    col1.iter().map(|x| x * counts.get(x).unwrap_or(&0)).sum()
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_columns(input).map(|_| ())
 }
 
-fn main() -> Result<(), Error> {
-    let input: Vec<_> = read_to_string(Path::new("data/input01.txt"))?
-        .lines()
-        .map(|line| {
-            line.split_whitespace()
-                .map(|value| value.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>()
+fn distance(col1: &[i32], col2: &[i32]) -> i64 {
+    zip(sorted(col1), sorted(col2))
+        .map(|(x, y)| (x as i64 - y as i64).abs())
+        .sum()
+}
+
+fn squared_distance(col1: &[i32], col2: &[i32]) -> i64 {
+    zip(sorted(col1), sorted(col2))
+        .map(|(x, y)| (x as i64 - y as i64).pow(2))
+        .sum()
+}
+
+fn similarity(col1: &[i32], col2: &[i32]) -> i64 {
+    let counts = Counter::from_keys(col2);
+    col1.iter().map(|x| *x as i64 * counts.get(&x) as i64).sum()
+}
+
+// 1-based ranks in row order, ties get the average of the ranks they span.
+fn ranks(col: &[i32]) -> Vec<f64> {
+    let order = (0..col.len()).sorted_by_key(|&i| col[i]).collect_vec();
+    let mut ranks = vec![0.0; col.len()];
+    let mut first = 0;
+    for group in order.chunk_by(|&i, &j| col[i] == col[j]) {
+        let rank = first as f64 + (group.len() + 1) as f64 / 2.0;
+        for &i in group {
+            ranks[i] = rank;
+        }
+        first += group.len();
+    }
+    ranks
+}
+
+// Spearman's rank correlation: the Pearson correlation of the rows' ranks. None when a column
+// is constant, as its ranks don't vary.
+fn rank_correlation(col1: &[i32], col2: &[i32]) -> Option<f64> {
+    let (ranks1, ranks2) = (ranks(col1), ranks(col2));
+    let mean = (col1.len() + 1) as f64 / 2.0;
+    let covariance: f64 = zip(&ranks1, &ranks2)
+        .map(|(x, y)| (x - mean) * (y - mean))
+        .sum();
+    let spread = |ranks: &[f64]| ranks.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+    let (spread1, spread2) = (spread(&ranks1), spread(&ranks2));
+    (spread1 > 0.0 && spread2 > 0.0).then(|| covariance / (spread1 * spread2).sqrt())
+}
+
+fn report(columns: &[Vec<i32>]) -> String {
+    (0..columns.len())
+        .tuple_combinations()
+        .flat_map(|(i, j)| {
+            METRICS.iter().map(move |(name, metric)| {
+                format!(
+                    "columns {} and {}: {} = {}\n",
+                    i + 1,
+                    j + 1,
+                    name,
+                    metric(&columns[i], &columns[j])
+                )
+            })
         })
-        .collect();
-    let col1 = get_col(&input, 0);
-    let col2 = get_col(&input, 1);
-    println!("{}", solve1(&col1, &col2));
-    println!("{}", solve2(&col1, &col2));
+        .collect()
+}
+
+fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let columns = parse_columns(&read_to_string(Path::new("data/input01.txt"))?)?;
+    if columns.len() < 2 {
+        return Err(Error::msg("At least two columns are needed"));
+    }
+    if std::env::args().any(|arg| arg == "--report") {
+        print!("{}", report(&columns));
+    } else {
+        println!("{}", distance(&columns[0], &columns[1]));
+        println!("{}", similarity(&columns[0], &columns[1]));
+    }
     Ok(())
 }
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            format!(
+                "{}   {}\n",
+                rng.range(10000..=99999),
+                rng.range(10000..=99999)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+
+    #[test]
+    fn example() {
+        let columns = parse_columns(EXAMPLE).unwrap();
+        assert_eq!(distance(&columns[0], &columns[1]), 11);
+        assert_eq!(similarity(&columns[0], &columns[1]), 31);
+        assert_eq!(squared_distance(&columns[0], &columns[1]), 35);
+    }
+
+    #[test]
+    fn rank_correlation_of_monotone_columns() {
+        assert_eq!(ranks(&[30, 10, 20, 10]), vec![4.0, 1.5, 3.0, 1.5]);
+        assert_eq!(rank_correlation(&[1, 2, 3], &[10, 40, 90]), Some(1.0));
+        assert_eq!(rank_correlation(&[1, 2, 3], &[9, 4, 1]), Some(-1.0));
+        assert_eq!(rank_correlation(&[1, 2, 3], &[5, 5, 5]), None);
+        assert_eq!(rank_correlation(&[7], &[8]), None);
+    }
+
+    #[test]
+    fn report_keeps_integers() {
+        let report = report(&parse_columns("1 4 2\n2 4 7\n").unwrap());
+        assert!(report.contains("columns 1 and 2: distance = 5\n"));
+        assert!(report.contains("columns 1 and 2: rank correlation = undefined\n"));
+        assert!(report.contains("columns 1 and 3: rank correlation = 1\n"));
+    }
+
+    #[test]
+    fn unequal_columns() {
+        let error = parse_columns("1 2 3\n4 5\n").unwrap_err();
+        assert_eq!(error.to_string(), "row 2: expected 3 columns, found 2");
+    }
+}
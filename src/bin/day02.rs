@@ -1,41 +1,380 @@
+pub mod utils;
+
 use anyhow::Error;
+use itertools::Itertools;
 use std::fs::read_to_string;
-use std::iter::zip;
 use std::path::Path;
+use std::str::FromStr;
+use utils::rand::{maybe_generate, Rng};
 
-fn is_safe(row: &[i32]) -> bool {
-    let acceptable = if row.last().unwrap() >= row.first().unwrap() {
-        |(a, b): (&i32, &i32)| a < b && *b <= a + 3
-    } else {
-        |(a, b): (&i32, &i32)| a > b && *b >= a - 3
-    };
-    zip(row.iter(), row.iter().skip(1)).all(acceptable)
+#[derive(Clone, Copy, Debug)]
+struct Tolerance {
+    min_step: i64,
+    max_step: i64,
+    max_removed: usize,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            min_step: 1,
+            max_step: 3,
+            max_removed: 0,
+        }
+    }
+}
+
+impl Tolerance {
+    fn allows(&self, direction: i64, a: i32, b: i32) -> bool {
+        (self.min_step..=self.max_step).contains(&((b as i64 - a as i64) * direction))
+    }
+}
+
+// Returns the fewest levels to remove (at most `max_removed`) so that the rest of the report is safe.
+fn dampen(row: &[i32], tolerance: &Tolerance) -> Option<Vec<usize>> {
+    if row.is_empty() {
+        return Some(vec![]);
+    }
+    [1, -1]
+        .iter()
+        .filter_map(|&direction| dampen_in_direction(row, tolerance, direction))
+        .min_by_key(|removed| removed.len())
+}
+
+// best[i] holds the fewest removals before level i when i is kept, and the previously kept level.
+// A kept level can only follow one of the k + 1 levels before it, so this is O(n * k).
+fn dampen_in_direction(row: &[i32], tolerance: &Tolerance, direction: i64) -> Option<Vec<usize>> {
+    let k = tolerance.max_removed;
+    let mut best: Vec<Option<(usize, Option<usize>)>> = Vec::with_capacity(row.len());
+    for i in 0..row.len() {
+        let mut entry = (i <= k).then_some((i, None));
+        for j in i.saturating_sub(k + 1)..i {
+            let Some((removed, _)) = best[j] else {
+                continue;
+            };
+            let removed = removed + i - j - 1;
+            if removed <= k
+                && tolerance.allows(direction, row[j], row[i])
+                && entry.is_none_or(|(fewest, _)| removed < fewest)
+            {
+                entry = Some((removed, Some(j)));
+            }
+        }
+        best.push(entry);
+    }
+
+    let (_, last) = (row.len().saturating_sub(k + 1)..row.len())
+        .filter_map(|i| best[i].map(|(removed, _)| (removed + row.len() - 1 - i, i)))
+        .filter(|&(removed, _)| removed <= k)
+        .min()?;
+    let mut kept = vec![false; row.len()];
+    let mut current = Some(last);
+    while let Some(i) = current {
+        kept[i] = true;
+        current = best[i].and_then(|(_, previous)| previous);
+    }
+    Some((0..row.len()).filter(|&i| !kept[i]).collect())
 }
 
-fn is_dampened_safe(row: &[i32]) -> bool {
-    (0..row.len()).any(|i| {
-        let start_iter = row.iter().take(i);
-        let end_iter = row.iter().skip(i + 1);
-        let sub_row: Vec<i32> = start_iter.chain(end_iter).cloned().collect();
-        is_safe(&sub_row)
+// The pair of levels at `index` and `index + 1` is the first one that breaks the rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Violation {
+    DirectionChange { index: usize, increasing: bool },
+    StepOutOfRange { index: usize, step: i64 },
+}
+
+impl Violation {
+    fn index(&self) -> usize {
+        match self {
+            Violation::DirectionChange { index, .. } | Violation::StepOutOfRange { index, .. } => {
+                *index
+            }
+        }
+    }
+
+    fn describe(&self, row: &[i32], tolerance: &Tolerance) -> String {
+        let index = self.index();
+        let pair = format!(
+            "levels {} and {} ({} -> {})",
+            index + 1,
+            index + 2,
+            row[index],
+            row[index + 1]
+        );
+        match self {
+            Violation::DirectionChange { increasing, .. } => format!(
+                "{} {} after the report started {}",
+                pair,
+                if *increasing { "decrease" } else { "increase" },
+                if *increasing {
+                    "increasing"
+                } else {
+                    "decreasing"
+                }
+            ),
+            Violation::StepOutOfRange { step, .. } => format!(
+                "{} differ by {}, outside {}..={}",
+                pair, step, tolerance.min_step, tolerance.max_step
+            ),
+        }
+    }
+}
+
+// The direction is set by the first pair that changes; a pair that doesn't change can only be
+// within range when `min_step` is 0.
+fn explain(row: &[i32], tolerance: &Tolerance) -> Option<Violation> {
+    let mut increasing = None;
+    row.windows(2).enumerate().find_map(|(index, pair)| {
+        let diff = pair[1] as i64 - pair[0] as i64;
+        if diff != 0 && *increasing.get_or_insert(diff > 0) != (diff > 0) {
+            return Some(Violation::DirectionChange {
+                index,
+                increasing: diff < 0,
+            });
+        }
+        let step = diff.abs();
+        (!(tolerance.min_step..=tolerance.max_step).contains(&step))
+            .then_some(Violation::StepOutOfRange { index, step })
     })
 }
 
-fn solve(input: &[Vec<i32>], safety_fn: fn(&[i32]) -> bool) -> i32 {
+// Prints the report with the violating pair underlined.
+fn annotate(row: &[i32], violation: &Violation, tolerance: &Tolerance) -> String {
+    let levels = row.iter().map(|level| level.to_string()).collect_vec();
+    let start: usize = levels[..violation.index()]
+        .iter()
+        .map(|l| l.len() + 1)
+        .sum();
+    let width = levels[violation.index()].len() + 1 + levels[violation.index() + 1].len();
+    format!(
+        "{}\n{}{} {}",
+        levels.join(" "),
+        " ".repeat(start),
+        "^".repeat(width),
+        violation.describe(row, tolerance)
+    )
+}
+
+fn solve(input: &[Vec<i32>], safety_fn: impl Fn(&[i32]) -> bool) -> i32 {
     input.iter().filter(|row| safety_fn(row)).count() as i32
 }
 
-fn main() -> Result<(), Error> {
-    let input: Vec<_> = read_to_string(Path::new("data/input02.txt"))?
+fn arg<T: FromStr>(name: &str) -> Result<Option<T>, Error>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    Ok(std::env::args()
+        .skip_while(|arg| arg != name)
+        .nth(1)
+        .map(|value| value.parse())
+        .transpose()?)
+}
+
+fn parse_input(input: &str) -> Result<Vec<Vec<i32>>, Error> {
+    input
         .lines()
         .map(|line| {
             line.split_whitespace()
-                .map(|value| value.parse::<i32>().unwrap())
-                .collect::<Vec<i32>>()
+                .map(|value| Ok(value.parse::<i32>()?))
+                .collect()
         })
-        .collect();
+        .collect()
+}
 
-    println!("{}", solve(&input, is_safe));
-    println!("{}", solve(&input, is_dampened_safe));
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
+fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let input = parse_input(&read_to_string(Path::new("data/input02.txt"))?)?;
+
+    let tolerance = Tolerance {
+        min_step: arg("--min-step")?.unwrap_or(1),
+        max_step: arg("--max-step")?.unwrap_or(3),
+        max_removed: 0,
+    };
+    let dampened = Tolerance {
+        max_removed: arg("--dampen")?.unwrap_or(1),
+        ..tolerance
+    };
+
+    println!("{}", solve(&input, |row| dampen(row, &tolerance).is_some()));
+    println!("{}", solve(&input, |row| dampen(row, &dampened).is_some()));
+
+    if std::env::args().any(|arg| arg == "--explain") {
+        for (line, row) in input.iter().enumerate() {
+            if let Some(violation) = explain(row, &tolerance) {
+                println!(
+                    "report {}:\n{}",
+                    line + 1,
+                    annotate(row, &violation, &tolerance)
+                );
+            }
+        }
+    }
+    if std::env::args().any(|arg| arg == "--removed") {
+        for (line, row) in input.iter().enumerate() {
+            match dampen(row, &dampened) {
+                Some(removed) if !removed.is_empty() => println!(
+                    "report {}: remove {}",
+                    line + 1,
+                    removed
+                        .iter()
+                        .map(|&i| format!("level {} ({})", i + 1, row[i]))
+                        .join(", ")
+                ),
+                _ => {}
+            }
+        }
+    }
     Ok(())
 }
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let direction = if rng.chance(0.5) { 1 } else { -1 };
+            let mut level = rng.range(20..=80);
+            (0..rng.range(5..=8))
+                .map(|_| {
+                    let current = level;
+                    level += if rng.chance(0.9) {
+                        direction * rng.range(1..=3)
+                    } else {
+                        rng.range(-4..=4)
+                    };
+                    current
+                })
+                .join(" ")
+                + "\n"
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::prop::{check, shrink_int, shrink_vec};
+
+    fn is_safe(row: &[i32], tolerance: &Tolerance) -> bool {
+        [1, -1].iter().any(|&direction| {
+            row.windows(2)
+                .all(|pair| tolerance.allows(direction, pair[0], pair[1]))
+        })
+    }
+
+    // Tries every way of removing up to `max_removed` levels.
+    fn fewest_removals(row: &[i32], tolerance: &Tolerance) -> Option<usize> {
+        (0..=tolerance.max_removed.min(row.len())).find(|&count| {
+            (0..row.len()).combinations(count).any(|removed| {
+                let rest = (0..row.len())
+                    .filter(|i| !removed.contains(i))
+                    .map(|i| row[i])
+                    .collect_vec();
+                is_safe(&rest, tolerance)
+            })
+        })
+    }
+
+    #[test]
+    fn example() {
+        let input =
+            parse_input("7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n")
+                .unwrap();
+        let tolerance = Tolerance::default();
+        let dampened = Tolerance {
+            max_removed: 1,
+            ..tolerance
+        };
+        assert_eq!(solve(&input, |row| dampen(row, &tolerance).is_some()), 2);
+        assert_eq!(solve(&input, |row| dampen(row, &dampened).is_some()), 4);
+        assert_eq!(
+            dampen(&input[3], &dampened).map(|removed| removed.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn explain_example() {
+        let tolerance = Tolerance::default();
+        assert_eq!(explain(&[7, 6, 4, 2, 1], &tolerance), None);
+        assert_eq!(
+            explain(&[1, 2, 7, 8, 9], &tolerance),
+            Some(Violation::StepOutOfRange { index: 1, step: 5 })
+        );
+        assert_eq!(
+            explain(&[1, 3, 2, 4, 5], &tolerance),
+            Some(Violation::DirectionChange {
+                index: 1,
+                increasing: true
+            })
+        );
+        assert_eq!(
+            explain(&[8, 6, 4, 4, 1], &tolerance),
+            Some(Violation::StepOutOfRange { index: 2, step: 0 })
+        );
+        assert_eq!(
+            annotate(
+                &[1, 3, 2, 4, 5],
+                &Violation::DirectionChange {
+                    index: 1,
+                    increasing: true
+                },
+                &tolerance
+            ),
+            "1 3 2 4 5\n  ^^^ levels 2 and 3 (3 -> 2) decrease after the report started increasing"
+        );
+    }
+
+    #[test]
+    fn dampen_matches_brute_force() {
+        let generate = |rng: &mut Rng| {
+            let min_step = rng.range(0..=2);
+            let tolerance = Tolerance {
+                min_step,
+                max_step: min_step + rng.range(0..=3),
+                max_removed: rng.below(4) as usize,
+            };
+            let mut level = rng.range(0..=20) as i32;
+            let row = (0..rng.range(0..=9))
+                .map(|_| {
+                    level += rng.range(-4..=4) as i32;
+                    level
+                })
+                .collect_vec();
+            (row, tolerance)
+        };
+        let shrink = |(row, tolerance): &(Vec<i32>, Tolerance)| {
+            shrink_vec(row, |&level| {
+                shrink_int(level as i64)
+                    .into_iter()
+                    .map(|l| l as i32)
+                    .collect()
+            })
+            .into_iter()
+            .map(|row| (row, *tolerance))
+            .collect()
+        };
+        check(generate, shrink, |(row, tolerance)| {
+            let removed = dampen(row, tolerance);
+            let rest = removed.as_ref().map(|removed| {
+                (0..row.len())
+                    .filter(|i| !removed.contains(i))
+                    .map(|i| row[i])
+                    .collect_vec()
+            });
+            let undampened = Tolerance {
+                max_removed: 0,
+                ..*tolerance
+            };
+            removed.as_ref().map(Vec::len) == fewest_removals(row, tolerance)
+                && explain(row, &undampened).is_none() == dampen(row, &undampened).is_some()
+                && rest.is_none_or(|rest| is_safe(&rest, tolerance))
+        });
+    }
+}
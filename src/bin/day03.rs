@@ -1,28 +1,323 @@
+pub mod utils;
+
 use anyhow::Error;
-use regex::Regex;
-use std::fs::read_to_string;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
 use std::path::Path;
+use utils::rand::{maybe_generate, Rng};
+
+const CHUNK_SIZE: usize = 1 << 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Instruction {
+    Mul(i64, i64),
+    Do,
+    Dont,
+}
+
+// An instruction is written as `name(arg,...)`, every argument being 1 to 3 digits.
+const MAX_ARITY: usize = 2;
+
+struct Spec {
+    name: &'static str,
+    arity: usize,
+    build: fn(&[i64]) -> Instruction,
+}
+
+const SPECS: [Spec; 3] = [
+    Spec {
+        name: "mul",
+        arity: 2,
+        build: |args| Instruction::Mul(args[0], args[1]),
+    },
+    Spec {
+        name: "do",
+        arity: 0,
+        build: |_| Instruction::Do,
+    },
+    Spec {
+        name: "don't",
+        arity: 0,
+        build: |_| Instruction::Dont,
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Token {
+    offset: usize,
+    instruction: Instruction,
+}
+
+enum Match {
+    Complete(Instruction, usize),
+    Prefix,
+    None,
+}
+
+impl Spec {
+    // Matches an instruction at the start of `bytes`; `Prefix` means more bytes are needed to tell.
+    fn match_start(&self, bytes: &[u8]) -> Match {
+        let mut i = 0;
+        let mut args = [0; MAX_ARITY];
+        for &expected in self.name.as_bytes().iter().chain(b"(") {
+            match bytes.get(i) {
+                None => return Match::Prefix,
+                Some(&byte) if byte == expected => i += 1,
+                _ => return Match::None,
+            }
+        }
+        for (n, arg) in args[..self.arity].iter_mut().enumerate() {
+            if n > 0 {
+                match bytes.get(i) {
+                    None => return Match::Prefix,
+                    Some(b',') => i += 1,
+                    _ => return Match::None,
+                }
+            }
+            let start = i;
+            while i - start < 3 && bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+            if i == bytes.len() {
+                return Match::Prefix;
+            }
+            if i == start {
+                return Match::None;
+            }
+            *arg = bytes[start..i]
+                .iter()
+                .fold(0, |acc, digit| 10 * acc + (digit - b'0') as i64);
+        }
+        match bytes.get(i) {
+            None => Match::Prefix,
+            Some(b')') => Match::Complete((self.build)(&args[..self.arity]), i + 1),
+            _ => Match::None,
+        }
+    }
+}
+
+// Scans memory one byte at a time. Only the bytes that could still start an instruction are kept,
+// so an instruction is reported at the leftmost offset where it completes, like a regex would.
+struct Scanner {
+    specs: &'static [Spec],
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+impl Scanner {
+    fn new(specs: &'static [Spec]) -> Self {
+        Self {
+            specs,
+            pending: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8, tokens: &mut Vec<Token>) {
+        self.pending.push(byte);
+        self.advance(false, tokens);
+    }
+
+    fn finish(&mut self, tokens: &mut Vec<Token>) {
+        self.advance(true, tokens);
+    }
 
-fn solve(input: &str) -> i64 {
-    let regex = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
-    regex
-        .captures_iter(input)
-        .map(|capture| {
-            (1..3)
-                .map(|i| capture[i].parse::<i64>().unwrap())
-                .product::<i64>()
-        })
-        .sum()
+    fn advance(&mut self, at_end: bool, tokens: &mut Vec<Token>) {
+        while !self.pending.is_empty() {
+            let mut waiting = false;
+            let mut complete = None;
+            for spec in self.specs {
+                match spec.match_start(&self.pending) {
+                    Match::Complete(instruction, len) => {
+                        complete = Some((instruction, len));
+                        break;
+                    }
+                    Match::Prefix => waiting = true,
+                    Match::None => {}
+                }
+            }
+            if let Some((instruction, len)) = complete {
+                tokens.push(Token {
+                    offset: self.offset,
+                    instruction,
+                });
+                self.pending.drain(..len);
+                self.offset += len;
+            } else if waiting && !at_end {
+                return;
+            } else {
+                self.pending.remove(0);
+                self.offset += 1;
+            }
+        }
+    }
 }
 
-fn solve_with_enabling(input: &str) -> i64 {
-    let regex = Regex::new(r"(?s)don't\(\).*?(do\(\)|$)").unwrap();
-    regex.split(input).map(solve).sum()
+struct Machine {
+    conditional: bool,
+    enabled: bool,
+    sum: i64,
+}
+
+impl Machine {
+    fn new(conditional: bool) -> Self {
+        Self {
+            conditional,
+            enabled: true,
+            sum: 0,
+        }
+    }
+
+    fn execute(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::Mul(a, b) => {
+                if self.enabled || !self.conditional {
+                    self.sum += a * b;
+                }
+            }
+            Instruction::Do => self.enabled = true,
+            Instruction::Dont => self.enabled = false,
+        }
+    }
+}
+
+// Evaluates memory chunk by chunk for both parts; between chunks only the scanner's pending bytes
+// are kept, so the memory use doesn't depend on the size of the dump.
+fn evaluate(
+    mut reader: impl Read,
+    chunk_size: usize,
+    mut on_token: impl FnMut(&Token),
+) -> Result<(i64, i64), Error> {
+    let mut buffer = vec![0; chunk_size];
+    let mut scanner = Scanner::new(&SPECS);
+    let mut machines = [Machine::new(false), Machine::new(true)];
+    let mut tokens = Vec::new();
+    let mut finished = false;
+    while !finished {
+        let read = match reader.read(&mut buffer) {
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for &byte in &buffer[..read] {
+            scanner.push(byte, &mut tokens);
+        }
+        if read == 0 {
+            finished = true;
+            scanner.finish(&mut tokens);
+        }
+        for token in tokens.drain(..) {
+            on_token(&token);
+            for machine in &mut machines {
+                machine.execute(&token.instruction);
+            }
+        }
+    }
+    Ok((machines[0].sum, machines[1].sum))
+}
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    evaluate(input.as_bytes(), 7, |_| {}).map(|_| ())
 }
 
 fn main() -> Result<(), Error> {
-    let input: String = read_to_string(Path::new("data/input03.txt"))?;
-    println!("{}", solve(&input));
-    println!("{}", solve_with_enabling(&input));
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let show_tokens = std::env::args().any(|arg| arg == "--tokens");
+    let (result1, result2) = evaluate(
+        File::open(Path::new("data/input03.txt"))?,
+        CHUNK_SIZE,
+        |token| {
+            if show_tokens {
+                println!("{:>8} {:?}", token.offset, token.instruction);
+            }
+        },
+    )?;
+    println!("{}\n{}", result1, result2);
     Ok(())
 }
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut memory = String::new();
+    while memory.len() < size {
+        let (a, b) = (rng.range(0..=999), rng.range(0..=999));
+        let fragment = match rng.below(10) {
+            0..=2 => format!("mul({},{})", a, b),
+            3 => "do()".to_string(),
+            4 => "don't()".to_string(),
+            5 => format!("mul({},{}]", a, b),
+            6 => format!("mul ( {} , {} )", a, b),
+            7 if rng.chance(0.1) => "\n".to_string(),
+            _ => (0..rng.range(1..=8))
+                .map(|_| *rng.choose(b"!@#$%^&*()[]{}<>+-?,:;'/ dmulnot"))
+                .map(char::from)
+                .collect(),
+        };
+        memory += &fragment;
+    }
+    memory + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(memory: &[u8]) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        evaluate(memory, memory.len().max(1), |token| tokens.push(*token)).unwrap();
+        tokens
+    }
+
+    fn run(tokens: &[Token], conditional: bool) -> i64 {
+        let mut machine = Machine::new(conditional);
+        for token in tokens {
+            machine.execute(&token.instruction);
+        }
+        machine.sum
+    }
+
+    #[test]
+    fn example() {
+        let memory = b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let tokens = scan(memory);
+        assert_eq!(
+            tokens.iter().map(|token| token.offset).collect::<Vec<_>>(),
+            vec![1, 20, 28, 48, 59, 64]
+        );
+        assert_eq!(tokens[1].instruction, Instruction::Dont);
+        assert_eq!(run(&tokens, false), 161);
+        assert_eq!(run(&tokens, true), 48);
+    }
+
+    #[test]
+    fn malformed_arguments() {
+        let tokens = scan(b"mul(1234,5)mul(12,34mul(4*mul(,1)mul(1,2 )do ()mul(7,8)");
+        assert_eq!(
+            tokens,
+            vec![Token {
+                offset: 47,
+                instruction: Instruction::Mul(7, 8)
+            }]
+        );
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let memory = b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let expected = scan(memory);
+        for chunk_size in 1..=memory.len() {
+            let mut tokens = Vec::new();
+            let sums = evaluate(&memory[..], chunk_size, |token| tokens.push(*token)).unwrap();
+            assert_eq!(sums, (161, 48));
+            assert_eq!(tokens, expected);
+        }
+    }
+
+    #[test]
+    fn arguments_fit_the_buffer() {
+        assert!(SPECS.iter().all(|spec| spec.arity <= MAX_ARITY));
+    }
+}
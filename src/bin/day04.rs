@@ -1,69 +1,280 @@
+pub mod utils;
+
 use anyhow::Error;
+use std::collections::HashSet;
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::aho_corasick::AhoCorasick;
+use utils::animate::ansi;
+use utils::image::Color;
+use utils::map::{Map, Pos};
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let input: Vec<_> = read_to_string(Path::new("data/input04.txt"))?
-        .lines()
-        .map(|line| line.chars().collect::<Vec<char>>())
-        .collect();
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let input = parse_input(&read_to_string(Path::new("data/input04.txt"))?)?;
 
     println!("{}", solve(&input));
     println!("{}", solve_x(&input));
+
+    let color = std::env::args().any(|arg| arg == "--color");
+    if color || std::env::args().any(|arg| arg == "--render") {
+        let words = ["XMAS"];
+        print!(
+            "\n{}",
+            render(
+                &input,
+                &word_cells(&find_words(&input, &words), &words),
+                color
+            )
+        );
+        print!(
+            "\n{}",
+            render(&input, &cross_cells(&find_crosses(&input)), color)
+        );
+    }
+
+    if let Some(words) = std::env::args().skip_while(|arg| arg != "--words").nth(1) {
+        let words: Vec<&str> = words.split(',').collect();
+        for hit in find_words(&input, &words) {
+            println!(
+                "{} at ({}, {}) going ({}, {})",
+                words[hit.word], hit.start.x, hit.start.y, hit.direction.x, hit.direction.y
+            );
+        }
+    }
     Ok(())
 }
 
-fn get_directions() -> Vec<(isize, isize)> {
-    (-1..=1)
-        .flat_map(|r| (-1..=1).map(move |c| (r, c)))
-        .filter(|(dx, dy)| *dx != 0 || *dy != 0)
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| *rng.choose(&['X', 'M', 'A', 'S']))
+                .chain(['\n'])
+                .collect::<String>()
+        })
         .collect()
 }
 
+fn parse_input(input: &str) -> Result<Vec<Vec<char>>, Error> {
+    let grid: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+    if grid.is_empty() || grid[0].is_empty() || grid.iter().any(|row| row.len() != grid[0].len()) {
+        return Err(Error::msg("Grid must be non-empty and rectangular"));
+    }
+    Ok(grid)
+}
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Hit {
+    word: usize,
+    start: Pos,
+    direction: Pos,
+}
+
+// Right, down and both downward diagonals; each line is also searched backwards.
+const LINE_DIRECTIONS: [Pos; 4] = [
+    Pos { x: 1, y: 0 },
+    Pos { x: 0, y: 1 },
+    Pos { x: 1, y: 1 },
+    Pos { x: -1, y: 1 },
+];
+
+fn inside(grid: &[Vec<char>], pos: &Pos) -> bool {
+    0 <= pos.y && pos.y < grid.len() as i64 && 0 <= pos.x && pos.x < grid[0].len() as i64
+}
+
+fn lines(grid: &[Vec<char>]) -> Vec<(Vec<Pos>, Pos)> {
+    let mut lines = vec![];
+    for direction in LINE_DIRECTIONS {
+        for y in 0..grid.len() as i64 {
+            for x in 0..grid[0].len() as i64 {
+                let mut pos = Pos { x, y };
+                if inside(grid, &(pos + direction * -1)) {
+                    continue;
+                }
+                let mut line = vec![];
+                while inside(grid, &pos) {
+                    line.push(pos);
+                    pos = pos + direction;
+                }
+                lines.push((line, direction));
+            }
+        }
+    }
+    lines
+}
+
+fn find_words<S: AsRef<str>>(grid: &[Vec<char>], words: &[S]) -> Vec<Hit> {
+    let automaton = AhoCorasick::new(words);
+    let mut hits = vec![];
+    for (mut line, direction) in lines(grid) {
+        for direction in [direction, direction * -1] {
+            let letters = line.iter().map(|pos| grid[pos.y as usize][pos.x as usize]);
+            for found in automaton.find_overlapping(letters) {
+                hits.push(Hit {
+                    word: found.word,
+                    start: line[found.start],
+                    direction,
+                });
+            }
+            line.reverse();
+        }
+    }
+    hits
+}
+
 fn solve(input: &[Vec<char>]) -> usize {
-    let nrows = input.len() as isize;
-    let ncols = input[0].len() as isize;
-    let directions = get_directions();
-
-    (0..nrows)
-        .map(|r| {
-            (0..ncols)
-                .map(|c| {
-                    directions
-                        .iter()
-                        .filter(|&(dr, dc)| {
-                            input[r as usize][c as usize] == 'X'
-                                && 0 <= r + 3 * dr
-                                && r + 3 * dr < nrows
-                                && 0 <= c + 3 * dc
-                                && c + 3 * dc < ncols
-                                && input[(r + dr) as usize][(c + dc) as usize] == 'M'
-                                && input[(r + 2 * dr) as usize][(c + 2 * dc) as usize] == 'A'
-                                && input[(r + 3 * dr) as usize][(c + 3 * dc) as usize] == 'S'
-                        })
-                        .count()
-                })
-                .sum::<usize>()
-        })
-        .sum()
+    find_words(input, &["XMAS"]).len()
 }
-fn solve_x(input: &[Vec<char>]) -> usize {
+
+// The centers of all crossed MAS.
+fn find_crosses(input: &[Vec<char>]) -> Vec<Pos> {
     let nrows = input.len();
     let ncols = input[0].len();
 
     (1..nrows - 1)
-        .map(|r| {
+        .flat_map(|r| {
             (1..ncols - 1)
-                .filter(|&c| {
+                .filter(move |&c| {
                     input[r][c] == 'A'
                         && has_xs(input[r - 1][c - 1], input[r + 1][c + 1])
                         && has_xs(input[r - 1][c + 1], input[r + 1][c - 1])
                 })
-                .count()
+                .map(move |c| Pos {
+                    x: c as i64,
+                    y: r as i64,
+                })
         })
-        .sum()
+        .collect()
+}
+
+fn solve_x(input: &[Vec<char>]) -> usize {
+    find_crosses(input).len()
 }
 
 fn has_xs(a: char, b: char) -> bool {
     a != b && [a, b].iter().all(|&c| c == 'M' || c == 'S')
 }
+
+fn word_cells<S: AsRef<str>>(hits: &[Hit], words: &[S]) -> HashSet<Pos> {
+    hits.iter()
+        .flat_map(|hit| {
+            (0..words[hit.word].as_ref().chars().count() as i64)
+                .map(move |i| hit.start + hit.direction * i)
+        })
+        .collect()
+}
+
+fn cross_cells(centers: &[Pos]) -> HashSet<Pos> {
+    centers
+        .iter()
+        .flat_map(|&center| {
+            [(0, 0), (-1, -1), (1, -1), (-1, 1), (1, 1)].map(|(x, y)| center + Pos { x, y })
+        })
+        .collect()
+}
+
+// Letters outside `cells` become '.' like in the puzzle, or gray when coloring.
+fn render(grid: &[Vec<char>], cells: &HashSet<Pos>, color: bool) -> String {
+    let map = Map {
+        size: Pos {
+            x: grid[0].len() as i64,
+            y: grid.len() as i64,
+        },
+        data: grid
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, &c)| {
+                        let pos = Pos {
+                            x: x as i64,
+                            y: y as i64,
+                        };
+                        (c, cells.contains(&pos))
+                    })
+                    .collect()
+            })
+            .collect(),
+    };
+    if color {
+        return ansi(&map, |&(c, found)| {
+            (c, if found { Color::YELLOW } else { Color::GRAY })
+        });
+    }
+    map.data
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&(c, found)| if found { c } else { '.' })
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX\n";
+
+    #[test]
+    fn example() {
+        let grid = parse_input(EXAMPLE).unwrap();
+        assert_eq!(solve(&grid), 18);
+        assert_eq!(solve_x(&grid), 9);
+    }
+
+    #[test]
+    fn render_with_dots() {
+        let grid = parse_input("MMMS\nXMAS\nMSAM\nSAXS\n").unwrap();
+        let words = ["XMAS"];
+        assert_eq!(
+            render(
+                &grid,
+                &word_cells(&find_words(&grid, &words), &words),
+                false
+            ),
+            "....\nXMAS\n....\n....\n"
+        );
+        let grid = parse_input("MXS.\nXAXX\nMXS.\n....\n").unwrap();
+        assert_eq!(
+            render(&grid, &cross_cells(&find_crosses(&grid)), false),
+            "M.S.\n.A..\nM.S.\n....\n"
+        );
+    }
+
+    #[test]
+    fn hits_in_all_directions() {
+        let grid = parse_input("SAMX\nAA..\nM.M.\nX..X\n").unwrap();
+        let hits = find_words(&grid, &["XMAS", "AM"]);
+        let xmas = |x, y, dx, dy| Hit {
+            word: 0,
+            start: Pos { x, y },
+            direction: Pos { x: dx, y: dy },
+        };
+        assert!(hits.contains(&xmas(3, 0, -1, 0)));
+        assert!(hits.contains(&xmas(0, 3, 0, -1)));
+        assert!(hits.contains(&xmas(3, 3, -1, -1)));
+        assert_eq!(hits.iter().filter(|hit| hit.word == 0).count(), 3);
+        assert_eq!(hits.iter().filter(|hit| hit.word == 1).count(), 5);
+    }
+
+    #[test]
+    fn rejects_degenerate_grids() {
+        for input in ["", "\n\n\n", "XMAS\n\nXMAS\n", "XMAS\nXMA\n"] {
+            assert!(parse_input(input).is_err(), "{:?}", input);
+        }
+    }
+}
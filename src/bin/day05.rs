@@ -1,75 +1,569 @@
-use anyhow::Error;
+pub mod utils;
+
+use anyhow::{anyhow, Error};
 use itertools::Itertools;
-use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let input = parse_input(&read_to_string(Path::new("data/input05.txt"))?);
-    let (solution1, solution2) = solve(&input);
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let input = parse_input(&read_to_string(Path::new("data/input05.txt"))?)?;
+    if std::env::args().any(|arg| arg == "--report") {
+        print_report(&input);
+    }
+    let (solution1, solution2) = solve(&input)?;
     println!("{}\n{}", solution1, solution2);
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut pages: Vec<i32> = (10..=98).collect();
+    rng.shuffle(&mut pages);
+    pages.truncate(size.clamp(5, 49));
+
+    let mut rules: Vec<_> = pages
+        .iter()
+        .tuple_combinations()
+        .map(|(a, b)| format!("{}|{}\n", a, b))
+        .collect();
+    rng.shuffle(&mut rules);
+
+    let updates: String = (0..size)
+        .map(|_| {
+            let len = (2 * rng.range(2..=11) as usize + 1).min((pages.len() - 1) | 1);
+            let mut update: Vec<_> = pages.clone();
+            rng.shuffle(&mut update);
+            update.truncate(len);
+            if rng.chance(0.5) {
+                update.sort_by_key(|page| pages.iter().position(|p| p == page));
+            }
+            update.iter().join(",") + "\n"
+        })
+        .collect();
+    rules.concat() + "\n" + &updates
+}
+
 struct Input {
     rules: HashSet<(i32, i32)>,
     updates: Vec<Vec<i32>>,
 }
 
-fn parse_input(input: &str) -> Input {
+fn parse_input(input: &str) -> Result<Input, Error> {
     let mut iter = input.lines();
     let rules = iter
         .by_ref()
         .take_while(|line| !line.is_empty())
         .map(|line| {
             line.split("|")
-                .map(|num| num.parse::<i32>().unwrap())
+                .map(|num| num.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
                 .collect_tuple()
-                .unwrap()
+                .ok_or_else(|| Error::msg(format!("Invalid rule: {}", line)))
         })
-        .collect();
+        .collect::<Result<_, Error>>()?;
     let updates = iter
-        .map(|line| {
-            line.split(",")
-                .map(|num| num.parse::<i32>().unwrap())
-                .collect()
+        .map(|line| line.split(",").map(|num| num.parse::<i32>()).collect())
+        .collect::<Result<_, _>>()?;
+    Ok(Input { rules, updates })
+}
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
+fn violated_rules(rules: &HashSet<(i32, i32)>, update: &[i32]) -> Vec<(i32, i32)> {
+    let page_position: HashMap<_, _> = update.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+    rules
+        .iter()
+        .filter(|&(page1, page2)| {
+            let pos1 = page_position.get(page1);
+            let pos2 = page_position.get(page2);
+            pos1.is_some_and(|p1| pos2.is_some_and(|p2| p1 > p2))
         })
-        .collect();
-    Input { rules, updates }
+        .copied()
+        .sorted()
+        .collect()
 }
 
 fn valid_ordering(rules: &HashSet<(i32, i32)>, update: &[i32]) -> bool {
-    let page_position: HashMap<_, _> = update.iter().enumerate().map(|(i, &v)| (v, i)).collect();
-    rules.iter().all(|&(page1, page2)| {
-        let pos1 = page_position.get(&page1);
-        let pos2 = page_position.get(&page2);
-        pos1.is_none_or(|p1| pos2.is_none_or(|p2| p1 < p2))
-    })
-}
-
-fn middle_update(rules: &HashSet<(i32, i32)>, updates: &[i32]) -> i32 {
-    let mut sorted_updates = updates.to_vec();
-    sorted_updates.sort_by(|&a, &b| {
-        if rules.contains(&(a, b)) {
-            Ordering::Less
+    violated_rules(rules, update).is_empty()
+}
+
+// A largest set of elements no two of which are related, for a strict partial order given as
+// the elements above each one. By Dilworth's theorem its size is the number of elements minus
+// a maximum matching between lower and upper ends of the relation; König's theorem recovers the
+// set itself from the vertices reachable from unmatched lower ends by alternating paths.
+fn max_antichain(above: &[Vec<usize>]) -> Vec<usize> {
+    fn augment(
+        lower: usize,
+        above: &[Vec<usize>],
+        visited: &mut [bool],
+        matched: &mut [Option<usize>],
+    ) -> bool {
+        for &upper in &above[lower] {
+            if !visited[upper] {
+                visited[upper] = true;
+                if matched[upper].is_none_or(|other| augment(other, above, visited, matched)) {
+                    matched[upper] = Some(lower);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    let n = above.len();
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+    let has_match = (0..n)
+        .map(|lower| augment(lower, above, &mut vec![false; n], &mut matched))
+        .collect_vec();
+
+    let mut reached_lower = vec![false; n];
+    let mut reached_upper = vec![false; n];
+    let mut stack = (0..n).filter(|&lower| !has_match[lower]).collect_vec();
+    while let Some(lower) = stack.pop() {
+        if std::mem::replace(&mut reached_lower[lower], true) {
+            continue;
+        }
+        for &upper in &above[lower] {
+            reached_upper[upper] = true;
+            stack.extend(matched[upper]);
+        }
+    }
+    (0..n)
+        .filter(|&i| reached_lower[i] && !reached_upper[i])
+        .collect()
+}
+
+// Pages that keep their place must not need to swap with each other, directly or through other
+// pages of the update, so the fewest moves is the number of pages outside a largest such set.
+fn pages_to_move(rules: &HashSet<(i32, i32)>, update: &[i32]) -> Result<Vec<i32>, OrderError> {
+    if let Some(&page) = update.iter().duplicates().next() {
+        return Err(OrderError::DuplicatePage(page));
+    }
+    // before[i][j]: the rules put update[i] somewhere before update[j].
+    let mut before = update
+        .iter()
+        .map(|&a| {
+            update
+                .iter()
+                .map(|&b| rules.contains(&(a, b)))
+                .collect_vec()
+        })
+        .collect_vec();
+    let n = update.len();
+    for k in 0..n {
+        for i in 0..n {
+            for j in 0..n {
+                before[i][j] |= before[i][k] && before[k][j];
+            }
+        }
+    }
+
+    let cyclic: HashSet<i32> = (0..n)
+        .filter(|&i| before[i][i])
+        .map(|i| update[i])
+        .collect();
+    if !cyclic.is_empty() {
+        let predecessors: HashMap<i32, Vec<i32>> = update
+            .iter()
+            .map(|&page| {
+                let pages = update
+                    .iter()
+                    .filter(|&&other| rules.contains(&(other, page)));
+                (page, pages.copied().collect())
+            })
+            .collect();
+        return Err(OrderError::Cycle(find_cycle(&predecessors, &cyclic)));
+    }
+
+    let violated = (0..n)
+        .map(|i| (i + 1..n).filter(|&j| before[j][i]).collect_vec())
+        .collect_vec();
+    let keep: HashSet<usize> = max_antichain(&violated).into_iter().collect();
+    Ok((0..n)
+        .filter(|i| !keep.contains(i))
+        .map(|i| update[i])
+        .collect())
+}
+
+struct RuleReport {
+    contradictions: Vec<Vec<i32>>,
+    redundant: Vec<(i32, i32)>,
+    unconstrained: Vec<i32>,
+}
+
+// Pages reachable from `page` by following rules, leaving out the rule `skip`.
+fn reachable(
+    successors: &HashMap<i32, Vec<i32>>,
+    page: i32,
+    skip: Option<(i32, i32)>,
+) -> HashSet<i32> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![page];
+    while let Some(current) = stack.pop() {
+        for &next in successors.get(&current).into_iter().flatten() {
+            if Some((current, next)) != skip && seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
+// Contradictions are groups of pages whose rules form a cycle. A rule is redundant when its
+// later page can still be reached from its earlier one without it.
+fn rule_report(input: &Input) -> RuleReport {
+    let mut successors: HashMap<i32, Vec<i32>> = HashMap::new();
+    for &(before, after) in &input.rules {
+        successors.entry(before).or_default().push(after);
+    }
+    let reach: HashMap<i32, HashSet<i32>> = successors
+        .keys()
+        .map(|&page| (page, reachable(&successors, page, None)))
+        .collect();
+    let reaches = |a: i32, b: i32| reach.get(&a).is_some_and(|pages| pages.contains(&b));
+
+    let contradictions = reach
+        .iter()
+        .filter(|&(&page, _)| reaches(page, page))
+        .map(|(&page, pages)| {
+            pages
+                .iter()
+                .filter(|&&other| reaches(other, page))
+                .copied()
+                .sorted()
+                .collect_vec()
+        })
+        .unique()
+        .sorted()
+        .collect();
+    let redundant = input
+        .rules
+        .iter()
+        .filter(|&&(before, after)| {
+            reachable(&successors, before, Some((before, after))).contains(&after)
+        })
+        .copied()
+        .sorted()
+        .collect();
+    let constrained: HashSet<i32> = input.rules.iter().flat_map(|&(a, b)| [a, b]).collect();
+    let unconstrained = input
+        .updates
+        .iter()
+        .flatten()
+        .filter(|page| !constrained.contains(page))
+        .copied()
+        .unique()
+        .sorted()
+        .collect();
+
+    RuleReport {
+        contradictions,
+        redundant,
+        unconstrained,
+    }
+}
+
+fn print_report(input: &Input) {
+    let report = rule_report(input);
+    let list = |items: Vec<String>| {
+        if items.is_empty() {
+            "none".to_string()
         } else {
-            Ordering::Greater
+            items.join(", ")
+        }
+    };
+    println!(
+        "contradictions: {}",
+        list(
+            report
+                .contradictions
+                .iter()
+                .map(|pages| format!("{{{}}}", pages.iter().join(" ")))
+                .collect()
+        )
+    );
+    println!(
+        "redundant rules: {}",
+        list(
+            report
+                .redundant
+                .iter()
+                .map(|(a, b)| format!("{}|{}", a, b))
+                .collect()
+        )
+    );
+    println!(
+        "unconstrained pages: {}",
+        list(
+            report
+                .unconstrained
+                .iter()
+                .map(|page| page.to_string())
+                .collect()
+        )
+    );
+
+    for (i, update) in input.updates.iter().enumerate() {
+        let violated = violated_rules(&input.rules, update);
+        if violated.is_empty() {
+            continue;
+        }
+        let moves = match pages_to_move(&input.rules, update) {
+            Ok(pages) => format!("move {}", pages.iter().join(", ")),
+            Err(e) => format!("can't be fixed, {}", e),
+        };
+        println!(
+            "update {} ({}): violates {}; {}",
+            i + 1,
+            update.iter().join(","),
+            violated
+                .iter()
+                .map(|(a, b)| format!("{}|{}", a, b))
+                .join(", "),
+            moves
+        );
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum OrderError {
+    Cycle(Vec<i32>),
+    Ambiguous(i32, i32),
+    DuplicatePage(i32),
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::Cycle(pages) => {
+                write!(f, "rules form a cycle: {}", pages.iter().join(" -> "))
+            }
+            OrderError::Ambiguous(a, b) => {
+                write!(f, "pages {} and {} are not ordered by any rule", a, b)
+            }
+            OrderError::DuplicatePage(page) => write!(f, "page {} appears twice", page),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+// Kahn's algorithm over the rules between the update's pages. The order is unique only if a
+// single page is ready at every step.
+fn topological_order(rules: &HashSet<(i32, i32)>, update: &[i32]) -> Result<Vec<i32>, OrderError> {
+    let mut predecessors: HashMap<i32, Vec<i32>> = HashMap::new();
+    for &page in update {
+        if predecessors.insert(page, vec![]).is_some() {
+            return Err(OrderError::DuplicatePage(page));
+        }
+    }
+    for (&a, &b) in update.iter().tuple_combinations() {
+        for (before, after) in [(a, b), (b, a)] {
+            if rules.contains(&(before, after)) {
+                predecessors.entry(after).or_default().push(before);
+            }
         }
-    });
-    sorted_updates[sorted_updates.len() / 2]
+    }
+
+    let mut remaining: HashMap<i32, usize> = predecessors
+        .iter()
+        .map(|(&page, before)| (page, before.len()))
+        .collect();
+    let mut order = Vec::with_capacity(update.len());
+    while !remaining.is_empty() {
+        let ready = update
+            .iter()
+            .filter(|page| remaining.get(page) == Some(&0))
+            .collect_vec();
+        match ready[..] {
+            [] => {
+                let remaining = remaining.keys().copied().collect();
+                return Err(OrderError::Cycle(find_cycle(&predecessors, &remaining)));
+            }
+            [&page] => {
+                remaining.remove(&page);
+                for (after, before) in &predecessors {
+                    if before.contains(&page) {
+                        if let Some(count) = remaining.get_mut(after) {
+                            *count -= 1;
+                        }
+                    }
+                }
+                order.push(page);
+            }
+            [&a, &b, ..] => return Err(OrderError::Ambiguous(a, b)),
+        }
+    }
+    Ok(order)
 }
 
-fn solve(input: &Input) -> (i32, i32) {
+// Every remaining page still has a remaining predecessor, so walking backwards has to revisit one.
+fn find_cycle(predecessors: &HashMap<i32, Vec<i32>>, remaining: &HashSet<i32>) -> Vec<i32> {
+    let mut path = vec![*remaining.iter().min().expect("some pages remain")];
+    loop {
+        let current = path[path.len() - 1];
+        let previous = *predecessors[&current]
+            .iter()
+            .filter(|page| remaining.contains(page))
+            .min()
+            .expect("a remaining page has a remaining predecessor");
+        if let Some(start) = path.iter().position(|&page| page == previous) {
+            let mut cycle = path[start..].to_vec();
+            cycle.reverse();
+            let smallest = cycle.iter().position_min().expect("cycle is not empty");
+            cycle.rotate_left(smallest);
+            cycle.push(cycle[0]);
+            return cycle;
+        }
+        path.push(previous);
+    }
+}
+
+fn middle_update(rules: &HashSet<(i32, i32)>, update: &[i32]) -> Result<i32, OrderError> {
+    let order = topological_order(rules, update)?;
+    Ok(order[order.len() / 2])
+}
+
+fn solve(input: &Input) -> Result<(i32, i32), Error> {
     input
         .updates
         .iter()
-        .map(|update| {
+        .enumerate()
+        .map(|(i, update)| {
             if valid_ordering(&input.rules, update) {
-                (update[update.len() / 2], 0)
+                Ok((update[update.len() / 2], 0))
             } else {
-                (0, middle_update(&input.rules, update))
+                middle_update(&input.rules, update)
+                    .map(|middle| (0, middle))
+                    .map_err(|e| anyhow!("update {} ({}): {}", i + 1, update.iter().join(","), e))
             }
         })
-        .fold((0, 0), |(a1, a2), (v1, v2)| (a1 + v1, a2 + v2))
+        .fold_ok((0, 0), |(a1, a2), (v1, v2)| (a1 + v1, a2 + v2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(pairs: &[(i32, i32)]) -> HashSet<(i32, i32)> {
+        pairs.iter().copied().collect()
+    }
+
+    #[test]
+    fn unique_order() {
+        let rules = rules(&[(97, 75), (75, 47), (47, 61), (61, 53), (97, 53)]);
+        assert_eq!(
+            topological_order(&rules, &[61, 75, 53, 97, 47]),
+            Ok(vec![97, 75, 47, 61, 53])
+        );
+    }
+
+    #[test]
+    fn minimal_moves() {
+        let rules = rules(&[(97, 75), (75, 47), (47, 61), (61, 53), (97, 53), (97, 47)]);
+        let update = [75, 97, 47, 61, 53];
+        assert_eq!(violated_rules(&rules, &update), vec![(97, 75)]);
+        assert_eq!(pages_to_move(&rules, &update).unwrap().len(), 1);
+        assert_eq!(
+            pages_to_move(&rules, &[53, 61, 47, 75, 97]).unwrap().len(),
+            4
+        );
+    }
+
+    #[test]
+    fn moves_with_incomplete_rules() {
+        let one_rule = rules(&[(1, 3)]);
+        let moved = pages_to_move(&one_rule, &[3, 1, 2]).unwrap();
+        assert!(moved == vec![3] || moved == vec![1], "{:?}", moved);
+        assert_eq!(pages_to_move(&one_rule, &[2, 1]), Ok(vec![]));
+
+        // 1 and 3 have no rule of their own, but 2 still forces 3 before 1.
+        let chain = rules(&[(3, 2), (2, 1)]);
+        assert_eq!(pages_to_move(&chain, &[1, 2, 3]).unwrap().len(), 2);
+        assert_eq!(pages_to_move(&chain, &[1, 4, 3]).unwrap().len(), 0);
+
+        let cyclic = rules(&[(1, 2), (2, 1)]);
+        assert_eq!(
+            pages_to_move(&cyclic, &[2, 1, 5]),
+            Err(OrderError::Cycle(vec![1, 2, 1]))
+        );
+        assert_eq!(
+            pages_to_move(&one_rule, &[3, 1, 3]),
+            Err(OrderError::DuplicatePage(3))
+        );
+    }
+
+    #[test]
+    fn antichains() {
+        // Divisibility among 1..=6: at most three numbers avoid dividing each other.
+        let above = (1..=6)
+            .map(|a| {
+                (0..6)
+                    .filter(|&j| j + 1 != a && (j + 1) % a == 0)
+                    .collect_vec()
+            })
+            .collect_vec();
+        let antichain = max_antichain(&above);
+        assert_eq!(antichain.len(), 3);
+        for (&i, &j) in antichain.iter().tuple_combinations() {
+            assert!(!above[i].contains(&j) && !above[j].contains(&i));
+        }
+        assert_eq!(max_antichain(&[vec![1, 2], vec![2], vec![]]).len(), 1);
+        assert!(max_antichain(&[]).is_empty());
+    }
+
+    #[test]
+    fn report() {
+        let input = parse_input("1|2\n2|3\n1|3\n4|5\n5|4\n\n1,2,3\n3,6\n").unwrap();
+        let report = rule_report(&input);
+        assert_eq!(report.contradictions, vec![vec![4, 5]]);
+        assert_eq!(report.redundant, vec![(1, 3)]);
+        assert_eq!(report.unconstrained, vec![6]);
+
+        // 3 is only reachable from 1 through the rule itself, not around the 1, 2 cycle.
+        let input = parse_input("1|2\n2|1\n1|3\n\n1,2,3\n").unwrap();
+        let report = rule_report(&input);
+        assert_eq!(report.contradictions, vec![vec![1, 2]]);
+        assert!(report.redundant.is_empty());
+    }
+
+    #[test]
+    fn cycle_and_ambiguity() {
+        let cyclic = rules(&[(1, 2), (2, 3), (3, 1), (4, 1)]);
+        assert_eq!(
+            topological_order(&cyclic, &[4, 3, 2, 1]),
+            Err(OrderError::Cycle(vec![1, 2, 3, 1]))
+        );
+        let partial = rules(&[(1, 2), (1, 3)]);
+        assert_eq!(
+            topological_order(&partial, &[3, 2, 1]),
+            Err(OrderError::Ambiguous(3, 2))
+        );
+        assert_eq!(
+            topological_order(&partial, &[1, 2, 1]),
+            Err(OrderError::DuplicatePage(1))
+        );
+    }
+
+    #[test]
+    fn generated_updates_have_a_middle_page() {
+        for size in [5, 6, 10] {
+            let input = parse_input(&generate(&mut Rng::new(size as u64), size)).unwrap();
+            let pages = size.clamp(5, 49);
+            assert!(input
+                .updates
+                .iter()
+                .all(|update| update.len() % 2 == 1 && update.len() <= pages));
+        }
+    }
 }
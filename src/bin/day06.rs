@@ -1,22 +1,53 @@
+pub mod utils;
+
 use anyhow::Error;
-use std::collections::HashSet;
 use std::fs::read_to_string;
 use std::ops::Index;
 use std::path::Path;
+use utils::bitmap::{BitMap, DirectionBitMap};
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let input = Input::from_str(&read_to_string(Path::new("data/input06.txt"))?);
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let input = Input::from_str(&read_to_string(Path::new("data/input06.txt"))?)?;
     let positions = get_positions(&input);
-    println!("{}", positions.len());
+    println!("{}", positions.count());
     println!("{}", solve2(&input, &positions));
     Ok(())
 }
 
-fn solve2(input: &Input, positions: &HashSet<Pos>) -> i32 {
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    loop {
+        let mut grid: Vec<Vec<char>> = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.chance(0.1) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let (row, col) = (rng.below(size as u64), rng.below(size as u64));
+        grid[row as usize][col as usize] = '^';
+        let text = grid
+            .iter()
+            .map(|line| line.iter().collect::<String>() + "\n")
+            .collect::<String>();
+        // Part one needs the guard to eventually leave the map.
+        if matches!(Input::from_str(&text), Ok(input) if !has_loop(&input)) {
+            return text;
+        }
+    }
+}
+
+fn solve2(input: &Input, positions: &BitMap) -> i32 {
     let mut changed_input = input.clone();
     positions
         .iter()
-        .filter(|&pos| {
+        .map(|pos| (pos.y as i32, pos.x as i32))
+        .filter(|pos| {
             if *pos != input.start && input.contains(pos) {
                 changed_input.map[pos.0 as usize][pos.1 as usize] = Tile::Obstacle;
                 let ret = has_loop(&changed_input);
@@ -33,32 +64,38 @@ fn has_loop(input: &Input) -> bool {
         pos: input.start,
         direction: Direction::Up,
     };
-    let mut seen: HashSet<State> = HashSet::new();
+    let mut seen = DirectionBitMap::new(input.map_size());
     loop {
         state = input.next_state(&state);
-        if seen.contains(&state) {
-            return true;
-        }
         if !input.contains(&state.pos) {
             return false;
         }
-        seen.insert(state.clone());
+        if !seen.insert(&map_pos(&state.pos), state.direction as usize) {
+            return true;
+        }
     }
 }
 
-fn get_positions(input: &Input) -> HashSet<Pos> {
+fn get_positions(input: &Input) -> BitMap {
     let mut state = State {
         pos: input.start,
         direction: Direction::Up,
     };
-    let mut seen: HashSet<Pos> = HashSet::new();
+    let mut seen = BitMap::new(input.map_size());
     while input.contains(&state.pos) {
+        seen.insert(&map_pos(&state.pos));
         state = input.next_state(&state);
-        seen.insert(state.pos);
     }
     seen
 }
 
+fn map_pos(pos: &Pos) -> utils::map::Pos {
+    utils::map::Pos {
+        x: pos.1 as i64,
+        y: pos.0 as i64,
+    }
+}
+
 #[derive(Eq, Hash, PartialEq, Clone, Copy)]
 enum Direction {
     Up,
@@ -100,7 +137,7 @@ struct Input {
 }
 
 impl Input {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> Result<Self, Error> {
         let mut start = None;
         let map: Vec<_> = input
             .lines()
@@ -109,23 +146,31 @@ impl Input {
                 line.chars()
                     .enumerate()
                     .map(|(col, c)| match c {
-                        '.' => Tile::Empty,
-                        '#' => Tile::Obstacle,
+                        '.' => Ok(Tile::Empty),
+                        '#' => Ok(Tile::Obstacle),
                         '^' => {
                             start = Some((row as i32, col as i32));
-                            Tile::Empty
+                            Ok(Tile::Empty)
                         }
-                        _ => unreachable!(),
+                        _ => Err(Error::msg(format!("Invalid tile character: {:?}", c))),
                     })
-                    .collect::<Vec<_>>()
+                    .collect::<Result<Vec<_>, _>>()
             })
-            .collect();
-        let size = (map.len() as i32, map[0].len() as i32);
-        Self {
+            .collect::<Result<_, _>>()?;
+        let width = map.first().ok_or(Error::msg("Empty map"))?.len();
+        if map.iter().any(|row| row.len() != width) {
+            return Err(Error::msg("Map rows have different lengths"));
+        }
+        let size = (map.len() as i32, width as i32);
+        Ok(Self {
             map,
-            start: start.unwrap(),
+            start: start.ok_or(Error::msg("Guard not found"))?,
             size,
-        }
+        })
+    }
+
+    fn map_size(&self) -> utils::map::Pos {
+        map_pos(&self.size)
     }
 
     fn contains(&self, pos: &Pos) -> bool {
@@ -147,6 +192,11 @@ impl Input {
     }
 }
 
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    Input::from_str(input).map(|_| ())
+}
+
 impl Index<&Pos> for Input {
     type Output = Tile;
 
@@ -1,8 +1,17 @@
+pub mod utils;
+
 use anyhow::Error;
-use std::collections::HashSet;
+use itertools::Itertools;
 use std::fs::read_to_string;
-use std::ops::{Add, Mul};
 use std::path::Path;
+use utils::math::concat;
+use utils::memo::Memo;
+use utils::rand::{maybe_generate, Rng};
+
+type Operator = fn(u64, u64) -> Option<u64>;
+
+// Part one uses the first two operators, part two all three.
+const OPERATORS: [Operator; 3] = [u64::checked_add, u64::checked_mul, concat];
 
 struct Equation {
     test: u64,
@@ -10,57 +19,54 @@ struct Equation {
 }
 
 impl Equation {
-    fn from_str(s: &str) -> Self {
-        let (test_str, numbers_str) = s.split_once(": ").unwrap();
-        let test = test_str.parse().unwrap();
-        let numbers = numbers_str.split(" ").map(|x| x.parse().unwrap()).collect();
-        Self { test, numbers }
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (test_str, numbers_str) = s
+            .split_once(": ")
+            .ok_or_else(|| Error::msg(format!("Invalid equation: {}", s)))?;
+        let test = test_str.parse()?;
+        let numbers = numbers_str
+            .split(" ")
+            .map(|x| x.parse())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { test, numbers })
     }
 
-    fn solvable(&self, ops: &[fn(u64, u64) -> u64]) -> bool {
-        self.numbers
-            .iter()
-            .fold(HashSet::from([0]), |acc, new| {
-                let mut ret = HashSet::new();
-                for acc_el in acc {
-                    for op in ops {
-                        let new_val = op(acc_el, *new);
-                        if new_val <= self.test {
-                            ret.insert(new_val);
-                        }
-                    }
-                }
-                ret
+    // Operators return None on overflow, which can never reach the test value.
+    fn solvable(&self, ops: &[Operator]) -> bool {
+        let mut reachable = Memo::new(|memo, (index, acc): (usize, u64)| {
+            let Some(&new) = self.numbers.get(index) else {
+                return acc == self.test;
+            };
+            ops.iter().any(|op| {
+                op(acc, new)
+                    .is_some_and(|new_val| new_val <= self.test && memo.get((index + 1, new_val)))
             })
-            .contains(&self.test)
+        });
+        reachable.get((0, 0))
     }
 }
 
-fn num_digits(mut val: u64) -> u32 {
-    let mut ret = 0;
-    while val > 0 {
-        ret += 1;
-        val /= 10;
-    }
-    ret
+fn parse_input(input: &str) -> Result<Vec<Equation>, Error> {
+    input.lines().map(Equation::from_str).collect()
 }
-fn concatenate(a: u64, b: u64) -> u64 {
-    a * 10_u64.pow(num_digits(b)) + b
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
 }
 
 fn main() -> Result<(), Error> {
-    let equations: Vec<_> = read_to_string(Path::new("data/input07.txt"))?
-        .lines()
-        .map(Equation::from_str)
-        .collect();
-    let ops1 = vec![u64::add, u64::mul];
-    let ops2 = vec![u64::add, u64::mul, concatenate];
-    for ops in [ops1, ops2] {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let equations = parse_input(&read_to_string(Path::new("data/input07.txt"))?)?;
+    for ops in [&OPERATORS[..2], &OPERATORS[..]] {
         println!(
             "{}",
             equations
                 .iter()
-                .filter(|&equation| equation.solvable(&ops))
+                .filter(|&equation| equation.solvable(ops))
                 .map(|equation| equation.test)
                 .sum::<u64>()
         );
@@ -68,3 +74,47 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut equations = String::new();
+    let mut count = 0;
+    while count < size {
+        let numbers: Vec<u64> = (0..rng.range(2..=8))
+            .map(|_| {
+                let max = if rng.chance(0.2) { 999 } else { 99 };
+                rng.range(1..=max) as u64
+            })
+            .collect();
+        let test = numbers[1..]
+            .iter()
+            .try_fold(numbers[0], |acc, &n| rng.choose(&OPERATORS)(acc, n))
+            .and_then(|test| test.checked_add(if rng.chance(0.3) { 1 } else { 0 }));
+        if let Some(test) = test {
+            equations += &format!("{}: {}\n", test, numbers.iter().join(" "));
+            count += 1;
+        }
+    }
+    equations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_is_unsolvable() {
+        let equation = Equation::from_str("18446744073709551615: 4294967296 4294967296 1").unwrap();
+        assert!(!equation.solvable(&OPERATORS));
+        let equation = Equation::from_str("18446744073709551615: 18446744073709551614 1").unwrap();
+        assert!(equation.solvable(&OPERATORS[..2]));
+    }
+
+    #[test]
+    fn generated_input_is_solvable() {
+        let equations = parse_input(&generate(&mut Rng::new(3), 300)).unwrap();
+        assert_eq!(equations.len(), 300);
+        let solvable = |ops| equations.iter().filter(|e| e.solvable(ops)).count();
+        assert!(solvable(&OPERATORS[..2]) <= solvable(&OPERATORS[..]));
+        assert!(solvable(&OPERATORS[..]) >= 150);
+    }
+}
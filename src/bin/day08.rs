@@ -1,12 +1,20 @@
+pub mod utils;
+
 use anyhow::Error;
 use itertools::Itertools;
 use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::ops::{Add, Div, Mul, Sub};
 use std::path::Path;
+use utils::math::gcd;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let map = Map::from_str(&read_to_string(Path::new("data/input08.txt"))?);
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let map = Map::from_str(&read_to_string(Path::new("data/input08.txt"))?)?;
 
     [get_antinodes, get_resonant_antinodes]
         .iter()
@@ -15,10 +23,28 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let frequencies: Vec<char> = ('0'..='9').chain('a'..='z').chain('A'..='Z').collect();
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| {
+                    if rng.chance(0.03) {
+                        *rng.choose(&frequencies[..8])
+                    } else {
+                        '.'
+                    }
+                })
+                .chain(['\n'])
+                .collect::<String>()
+        })
+        .collect()
+}
+
 fn solve(map: &Map, antinode_fn: fn(&[Pos], &Map) -> Vec<Pos>) -> usize {
     map.antennas
-        .iter()
-        .flat_map(|(_ch, positions)| antinode_fn(positions, map))
+        .values()
+        .flat_map(|positions| antinode_fn(positions, map))
         .unique()
         .count()
 }
@@ -121,10 +147,14 @@ struct Map {
 }
 
 impl Map {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> Result<Self, Error> {
         let lines = input.lines().collect::<Vec<&str>>();
         let size = Pos {
-            x: lines[0].chars().count() as i32,
+            x: lines
+                .first()
+                .ok_or(Error::msg("Empty map"))?
+                .chars()
+                .count() as i32,
             y: lines.len() as i32,
         };
         let antennas = lines
@@ -152,7 +182,7 @@ impl Map {
                     .or_insert(vec![pos]);
                 antennas
             });
-        Self { size, antennas }
+        Ok(Self { size, antennas })
     }
 
     fn contains(&self, pos: &Pos) -> bool {
@@ -160,13 +190,7 @@ impl Map {
     }
 }
 
-fn gcd(mut a: i32, mut b: i32) -> i32 {
-    a = a.abs();
-    b = b.abs();
-    while b != 0 {
-        let temp = b;
-        b = a % b;
-        a = temp;
-    }
-    a
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    Map::from_str(input).map(|_| ())
 }
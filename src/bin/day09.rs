@@ -1,3 +1,5 @@
+pub mod utils;
+
 use anyhow::Error;
 use itertools::{repeat_n, Itertools};
 use std::cmp::{PartialEq, Reverse};
@@ -5,14 +7,34 @@ use std::collections::BinaryHeap;
 use std::fmt::Debug;
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let input = parse_input(&read_to_string(Path::new("data/input09.txt"))?);
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let input = parse_input(&read_to_string(Path::new("data/input09.txt"))?)?;
     println!("{}", solve(&input));
     println!("{}", solve2(&input));
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    // An odd number of digits, so the disk map starts and ends with a (non-empty) file.
+    (0..size.max(3) | 1)
+        .map(|i| {
+            let length = if i % 2 == 0 {
+                rng.range(1..=9)
+            } else {
+                rng.range(0..=9)
+            };
+            char::from(b'0' + length as u8)
+        })
+        .chain(['\n'])
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Block {
     Id(u64),
@@ -49,7 +71,10 @@ fn checksum(blocks: &[Block]) -> u64 {
 
 fn solve(input: &[u64]) -> u64 {
     let mut blocks = blocks_from_input(input);
-    let mut index = 0_usize;
+    // Without free space nothing moves.
+    let Some(mut index) = blocks.iter().position(|block| *block == Block::Empty) else {
+        return checksum(&blocks);
+    };
     let mut rindex = blocks.len() - 1;
     loop {
         while matches!(blocks[index], Block::Id(_)) {
@@ -129,10 +154,46 @@ fn solve2(input: &[u64]) -> u64 {
     checksum(&blocks)
 }
 
-fn parse_input(input: &str) -> Vec<u64> {
+fn parse_input(input: &str) -> Result<Vec<u64>, Error> {
     input
         .trim()
         .chars()
-        .map(|c| c.to_digit(10).unwrap() as u64)
+        .map(|c| {
+            c.to_digit(10)
+                .map(u64::from)
+                .ok_or_else(|| Error::msg(format!("Invalid digit: {:?}", c)))
+        })
         .collect()
 }
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let input = parse_input("2333133121414131402\n").unwrap();
+        assert_eq!(solve(&input), 1928);
+        assert_eq!(solve2(&input), 2858);
+    }
+
+    #[test]
+    fn generated_input_is_solvable() {
+        for size in 0..=6 {
+            for seed in 0..20 {
+                let input = parse_input(&generate(&mut Rng::new(seed), size)).unwrap();
+                assert!(input.len() >= 3 && input.len() % 2 == 1);
+                solve(&input);
+                solve2(&input);
+            }
+        }
+        // No free space at all.
+        assert_eq!(solve(&[2, 0, 3]), 2 + 3 + 4);
+        assert_eq!(solve2(&[2, 0, 3]), 2 + 3 + 4);
+    }
+}
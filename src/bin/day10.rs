@@ -1,12 +1,19 @@
+pub mod utils;
+
 use anyhow::Error;
 use itertools::Itertools;
-use std::collections::HashMap;
 use std::fs::read_to_string;
-use std::ops::Index;
 use std::path::Path;
+use utils::counter::Counter;
+use utils::map::{Map, Pos};
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
-    let map = Map::from_str(&read_to_string(Path::new("data/input10.txt"))?)?;
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
+    let map = Map::<u8>::from_str(&read_to_string(Path::new("data/input10.txt"))?)?;
     for score_fn in [trailhead_score, trailhead_rating] {
         println!("{}", solve(&map, score_fn));
     }
@@ -14,24 +21,33 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
-fn solve(map: &Map, score_fn: fn(&Map, &Pos) -> u32) -> u32 {
-    let start_points = map.data.iter().enumerate().flat_map(|(y, row)| {
-        row.iter().enumerate().filter_map(move |(x, &height)| {
-            if height == 0 {
-                Some(Pos {
-                    x: x as i32,
-                    y: y as i32,
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    // Mostly smooth slopes, so the map actually contains hiking trails.
+                    let height = (x + y) as i64 + rng.range(-1..=1);
+                    char::from(b'0' + height.rem_euclid(10) as u8)
                 })
-            } else {
-                None
-            }
+                .chain(['\n'])
+                .collect::<String>()
         })
-    });
+        .collect()
+}
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    Map::<u8>::from_str(input).map(|_| ())
+}
 
-    start_points.map(|pos| score_fn(map, &pos)).sum()
+fn solve(map: &Map<u8>, score_fn: fn(&Map<u8>, &Pos) -> u32) -> u32 {
+    map.positions_where(|&height| height == 0)
+        .map(|pos| score_fn(map, &pos))
+        .sum()
 }
 
-fn trailhead_score(map: &Map, start: &Pos) -> u32 {
+fn trailhead_score(map: &Map<u8>, start: &Pos) -> u32 {
     let mut positions = vec![*start];
     for i in 1..10 {
         positions = positions
@@ -45,86 +61,19 @@ fn trailhead_score(map: &Map, start: &Pos) -> u32 {
     positions.len() as u32
 }
 
-fn trailhead_rating(map: &Map, start: &Pos) -> u32 {
-    let mut positions: HashMap<Pos, u32> = HashMap::from([(*start, 1)]);
+fn trailhead_rating(map: &Map<u8>, start: &Pos) -> u32 {
+    let mut positions = Counter::from_keys([*start]);
     for i in 1..10 {
         positions = positions
             .iter()
-            .flat_map(|(pos, &count)| {
+            .flat_map(|(pos, count)| {
                 map.neighbors(pos)
-                    .iter()
-                    .map(move |neighbor| (*neighbor, count))
-                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(move |neighbor| (neighbor, count))
             })
             .filter(|(pos, _)| map[pos] == i)
-            .fold(HashMap::new(), |mut acc, (pos, count)| {
-                acc.entry(pos).and_modify(|c| *c += count).or_insert(count);
-                acc
-            });
-    }
-
-    positions.values().sum()
-}
-
-#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
-struct Pos {
-    x: i32,
-    y: i32,
-}
-
-struct Map {
-    size: Pos,
-    data: Vec<Vec<u8>>,
-}
-
-impl Map {
-    fn from_str(s: &str) -> Result<Self, Error> {
-        let data: Vec<Vec<u8>> = s
-            .lines()
-            .map(|l| {
-                l.chars()
-                    .map(|c| {
-                        c.to_digit(10)
-                            .map(|d| d as u8)
-                            .ok_or(Error::msg("non-digit found in input"))
-                    })
-                    .collect()
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        let size = Pos {
-            x: data.first().ok_or(Error::msg("Empty data"))?.len() as i32,
-            y: data.len() as i32,
-        };
-        Ok(Self { data, size })
-    }
-    fn contains(&self, pos: &Pos) -> bool {
-        0 <= pos.x && pos.x < self.size.y && 0 <= pos.y && pos.y < self.size.y
-    }
-
-    fn neighbors(&self, pos: &Pos) -> Vec<Pos> {
-        [-1, 1]
-            .iter()
-            .flat_map(|i| {
-                [
-                    Pos {
-                        x: pos.x + i,
-                        y: pos.y,
-                    },
-                    Pos {
-                        x: pos.x,
-                        y: pos.y + i,
-                    },
-                ]
-            })
-            .filter(|pos| self.contains(pos))
-            .collect()
+            .collect();
     }
-}
-
-impl Index<&Pos> for Map {
-    type Output = u8;
 
-    fn index(&self, pos: &Pos) -> &Self::Output {
-        &self.data[pos.y as usize][pos.x as usize]
-    }
+    positions.total() as u32
 }
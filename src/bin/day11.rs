@@ -1,59 +1,58 @@
+pub mod utils;
+
 use anyhow::Error;
-use std::collections::HashMap;
+use itertools::Itertools;
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::math::split_digits;
+use utils::memo::Memo;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let input = parse_input(&read_to_string(Path::new("data/input11.txt"))?)?;
 
+    let mut stones = Memo::new(count_stones);
     for num_iterations in [25, 75] {
-        println!("{}", solve(&input, num_iterations));
+        println!("{}", solve(&input, num_iterations, &mut stones));
+    }
+    if std::env::args().any(|arg| arg == "--stats") {
+        eprintln!("{}", stones.stats());
     }
 
     Ok(())
 }
 
-fn num_digits(mut n: u64) -> u32 {
-    let mut ret = 0;
-    while n > 0 {
-        n /= 10;
-        ret += 1;
-    }
-    ret
-}
-
-fn split_number(n: u64) -> Option<(u64, u64)> {
-    let digits = num_digits(n);
-    if digits % 2 == 0 {
-        let mask = 10_u64.pow(digits / 2);
-        Some((n / mask, n % mask))
-    } else {
-        None
-    }
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size).map(|_| rng.range(0..=999999)).join(" ") + "\n"
 }
 
 fn blink(n: u64) -> Vec<u64> {
     if n == 0 {
         vec![1]
-    } else if let Some((a, b)) = split_number(n) {
+    } else if let Some((a, b)) = split_digits(n) {
         vec![a, b]
     } else {
         vec![n * 2024]
     }
 }
 
-fn solve(input: &[u64], num_iterations: u32) -> usize {
-    let mut numbers: HashMap<u64, usize> = input.iter().map(|&n| (n, 1)).collect();
-    for _ in 0..num_iterations {
-        numbers = numbers
-            .iter()
-            .flat_map(|(&n, &count)| blink(n).iter().map(|&n| (n, count)).collect::<Vec<_>>())
-            .fold(HashMap::new(), |mut acc, (n, count)| {
-                acc.entry(n).and_modify(|e| *e += count).or_insert(count);
-                acc
-            })
+fn count_stones(memo: &mut Memo<(u64, u32), usize>, (n, num_iterations): (u64, u32)) -> usize {
+    if num_iterations == 0 {
+        1
+    } else {
+        blink(n)
+            .into_iter()
+            .map(|n| memo.get((n, num_iterations - 1)))
+            .sum()
     }
-    numbers.values().sum()
+}
+
+fn solve(input: &[u64], num_iterations: u32, stones: &mut Memo<(u64, u32), usize>) -> usize {
+    input.iter().map(|&n| stones.get((n, num_iterations))).sum()
 }
 
 fn parse_input(input: &str) -> Result<Vec<u64>, Error> {
@@ -62,3 +61,8 @@ fn parse_input(input: &str) -> Result<Vec<u64>, Error> {
         .map(|number| number.parse::<u64>())
         .collect::<Result<Vec<_>, _>>()?)
 }
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
@@ -1,13 +1,19 @@
 pub mod utils;
 
-use crate::utils::map::Pos;
 use anyhow::Error;
 use std::collections::HashSet;
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::bitmap::BitMap;
 use utils::map::Map;
+use utils::map::Pos;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let input = Map::<char>::from_str(&read_to_string(Path::new("data/input12.txt"))?)?;
 
     let (sol1, sol2) = solve(&input);
@@ -16,25 +22,41 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut grid = vec![vec!['A'; size]; size];
+    for y in 0..size {
+        for x in 0..size {
+            // Copy a neighbour most of the time, to grow larger regions.
+            grid[y][x] = match rng.below(10) {
+                0..=3 if x > 0 => grid[y][x - 1],
+                4..=6 if y > 0 => grid[y - 1][x],
+                _ => char::from(b'A' + rng.below(6) as u8),
+            };
+        }
+    }
+    grid.iter()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    Map::<char>::from_str(input).map(|_| ())
+}
+
 fn solve(map: &Map<char>) -> (u64, u64) {
-    let mut processed = Map {
-        size: map.size,
-        data: vec![vec![false; map.size.x as usize]; map.size.y as usize],
-    };
+    let mut processed = BitMap::new(map.size);
 
     let mut result1 = 0;
     let mut result2 = 0;
 
-    for y in 0..map.size.y {
-        for x in 0..map.size.x {
-            let pos = Pos { x, y };
-            if processed[&pos] {
-                continue;
-            }
-            let (area, num_borders, num_sides) = compute_price(map, pos, &mut processed);
-            result1 += area * num_borders;
-            result2 += area * num_sides;
+    for (pos, _) in map.iter_pos() {
+        if processed.contains(&pos) {
+            continue;
         }
+        let (area, num_borders, num_sides) = compute_price(map, pos, &mut processed);
+        result1 += area * num_borders;
+        result2 += area * num_sides;
     }
 
     (result1, result2)
@@ -65,14 +87,14 @@ fn get_direction(pos: &Pos, neighbour: &Pos) -> Direction {
     }
 }
 
-fn compute_price(map: &Map<char>, start: Pos, processed: &mut Map<bool>) -> (u64, u64, u64) {
+fn compute_price(map: &Map<char>, start: Pos, processed: &mut BitMap) -> (u64, u64, u64) {
     let mut to_process = vec![start];
     let mut area = 0;
     let mut border = 0;
     let mut borders = HashSet::new();
     let val = map[&start];
     while let Some(current) = to_process.pop() {
-        if processed[&current] {
+        if !processed.insert(&current) {
             continue;
         }
         area += 1;
@@ -100,7 +122,6 @@ fn compute_price(map: &Map<char>, start: Pos, processed: &mut Map<bool>) -> (u64
                 borders.insert((current, Direction::Down));
             }
         }
-        processed[&current] = true;
     }
 
     let num_sides = get_num_sides(borders);
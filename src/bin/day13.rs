@@ -1,9 +1,18 @@
+pub mod utils;
+
 use anyhow::Error;
+use itertools::Itertools;
 use regex::{Captures, Regex};
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::math::solve_2x2;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let configs = parse_input(&read_to_string(Path::new("data/input13.txt"))?)?;
 
     println!("{}", configs.iter().map(solve_direct).sum::<i64>());
@@ -18,6 +27,24 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let [ax, ay, bx, by] = [(); 4].map(|_| rng.range(10..=99));
+            let (px, py) = if rng.chance(0.5) {
+                let (a, b) = (rng.range(0..=99), rng.range(0..=99));
+                (a * ax + b * bx, a * ay + b * by)
+            } else {
+                (rng.range(1000..=20000), rng.range(1000..=20000))
+            };
+            format!(
+                "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+                ax, ay, bx, by, px, py
+            )
+        })
+        .join("\n")
+}
+
 #[derive(Clone)]
 struct Config {
     ax: i64,
@@ -36,34 +63,27 @@ fn config_part_two(config: &Config) -> Config {
     ret
 }
 
-#[allow(dead_code)]
+// Tries every number of A presses that doesn't overshoot X; needs positive button steps.
+#[cfg(test)]
 fn solve_brute_force(config: &Config) -> i64 {
-    (0..100)
-        .flat_map(|a| {
-            (0..100).filter_map(move |b| {
-                if a * config.ax + b * config.bx == config.px
-                    && a * config.ay + b * config.by == config.py
-                {
-                    Some(3 * a + b)
-                } else {
-                    None
-                }
-            })
+    (0..=config.px / config.ax)
+        .filter_map(|a| {
+            let rest = config.px - a * config.ax;
+            let b = rest / config.bx;
+            (rest % config.bx == 0 && a * config.ay + b * config.by == config.py)
+                .then_some(3 * a + b)
         })
         .min()
         .unwrap_or(0)
 }
 
 fn solve_direct(c: &Config) -> i64 {
-    let d = c.ax * c.by - c.ay * c.bx;
-    let da = c.px * c.by - c.py * c.bx;
-    let db = c.ax * c.py - c.ay * c.px;
-    if da % d == 0 && db % d == 0 {
-        let a = da / d;
-        let b = db / d;
-        3 * a + b
-    } else {
-        0
+    let Some([a, b]) = solve_2x2([[c.ax, c.bx], [c.ay, c.by]], [c.px, c.py]) else {
+        return 0;
+    };
+    match (a.to_integer(), b.to_integer()) {
+        (Some(a), Some(b)) if a >= 0 && b >= 0 => (3 * a + b) as i64,
+        _ => 0,
     }
 }
 
@@ -78,7 +98,7 @@ fn parse_input(s: &str) -> Result<Vec<Config>, Error> {
                 .map(|s| s.parse::<i64>())
                 .collect::<Result<Vec<i64>, _>>()?
                 .try_into()
-                .unwrap();
+                .map_err(|_| Error::msg("Expected six numbers"))?;
 
             Ok(Config {
                 ax,
@@ -91,3 +111,105 @@ fn parse_input(s: &str) -> Result<Vec<Config>, Error> {
         })
         .collect()
 }
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::prop::{self, check_with, shrink_toward};
+    use crate::utils::rand::Rng;
+    // Generated from button presses, so most machines have a solution; an offset usually breaks it.
+    // Generated from button presses, so the brute force search range covers every solution.
+    #[derive(Clone, Debug)]
+    struct Machine {
+        buttons: [i64; 4],
+        presses: [i64; 2],
+        offset: i64,
+    }
+
+    impl Machine {
+        fn config(&self) -> Config {
+            let [ax, ay, bx, by] = self.buttons;
+            let [a, b] = self.presses;
+            Config {
+                ax,
+                ay,
+                bx,
+                by,
+                px: a * ax + b * bx + self.offset,
+                py: a * ay + b * by,
+            }
+        }
+
+        fn is_valid(&self) -> bool {
+            let [ax, ay, bx, by] = self.buttons;
+            ax * by != ay * bx
+        }
+
+        fn generate(rng: &mut Rng) -> Self {
+            loop {
+                let machine = Machine {
+                    buttons: [(); 4].map(|_| rng.range(1..=99)),
+                    presses: [(); 2].map(|_| rng.range(0..=99)),
+                    offset: if rng.chance(0.5) { 0 } else { rng.range(1..=3) },
+                };
+                if machine.is_valid() {
+                    return machine;
+                }
+            }
+        }
+
+        fn shrink(&self) -> Vec<Self> {
+            let mut candidates = vec![];
+            for i in 0..4 {
+                for button in shrink_toward(self.buttons[i], 1) {
+                    let mut machine = self.clone();
+                    machine.buttons[i] = button;
+                    candidates.push(machine);
+                }
+            }
+            for i in 0..2 {
+                for presses in shrink_toward(self.presses[i], 0) {
+                    let mut machine = self.clone();
+                    machine.presses[i] = presses;
+                    candidates.push(machine);
+                }
+            }
+            for offset in shrink_toward(self.offset, 0) {
+                candidates.push(Machine {
+                    offset,
+                    ..self.clone()
+                });
+            }
+            candidates.retain(Machine::is_valid);
+            candidates
+        }
+    }
+
+    #[test]
+    fn direct_matches_brute_force() {
+        for seed in 0..32 {
+            let config = prop::Config {
+                seed,
+                ..prop::Config::default()
+            };
+            check_with(config, Machine::generate, Machine::shrink, |machine| {
+                solve_direct(&machine.config()) == solve_brute_force(&machine.config())
+            });
+        }
+    }
+
+    #[test]
+    fn negative_presses_win_no_prize() {
+        let machine = Machine {
+            buttons: [49, 64, 55, 72],
+            presses: [0, 0],
+            offset: 1,
+        };
+        assert_eq!(solve_direct(&machine.config()), 0);
+    }
+}
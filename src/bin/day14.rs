@@ -1,23 +1,49 @@
-use crate::utils::map::{Map, Pos};
 use anyhow::Error;
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
-use std::fs::{read_to_string, File};
-use std::io::BufWriter;
-use std::io::Write;
+use std::fs::read_to_string;
 use std::path::Path;
+use utils::cycle::brent;
+use utils::image::{Color, Image};
+use utils::map::Pos;
+use utils::rand::{maybe_generate, Rng};
+use utils::record::Recorder;
+use utils::torus::TorusMap;
 
 pub mod utils;
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let input = Robot::from_multi_str(&read_to_string(Path::new("data/input14.txt"))?)?;
 
+    let output = std::env::args()
+        .skip_while(|arg| arg != "--record")
+        .nth(1)
+        .unwrap_or("data/output14.gif".to_string());
+
     println!("{}", solve(&input, Pos { x: 101, y: 103 }));
-    display_trees(&input, Pos { x: 101, y: 103 });
+    display_trees(&input, Pos { x: 101, y: 103 }, Path::new(&output))?;
 
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            format!(
+                "p={},{} v={},{}\n",
+                rng.range(0..=100),
+                rng.range(0..=102),
+                rng.range(-99..=99),
+                rng.range(-99..=99)
+            )
+        })
+        .collect()
+}
+
 fn get_quadrant(pos: &Pos, size: &Pos) -> Option<usize> {
     let half_width = size.x / 2;
     let half_height = size.y / 2;
@@ -44,30 +70,44 @@ fn solve(robots: &[Robot], size: Pos) -> i64 {
         .product()
 }
 
-fn display_trees(robots: &[Robot], size: Pos) {
-    let file = File::create("data/output14.txt").unwrap();
-    let mut f = BufWriter::new(file);
-    for i in 0..10000 {
-        writeln!(&mut f, "Iteration {}", i).unwrap();
-        let positions = robots
+fn display_trees(robots: &[Robot], size: Pos, output: &Path) -> Result<(), Error> {
+    let start: Vec<_> = robots.iter().map(|robot| robot.position).collect();
+    let floor = TorusMap::new(size, ());
+    let step = |positions: &Vec<Pos>| {
+        positions
             .iter()
-            .map(|robot| (robot.position + robot.velocity * i) % size)
-            .collect::<Vec<_>>();
-        display(&mut f, &positions, &size);
+            .zip(robots)
+            .map(|(&position, robot)| floor.wrap(&(position + robot.velocity)))
+            .collect::<Vec<_>>()
+    };
+    let cycle = brent(&start, step);
+
+    let mut recorder = Recorder::from_path(output, 10)?;
+    let mut positions = start;
+    for _ in 0..cycle.prefix + cycle.length {
+        recorder.push(&display(&positions, &size))?;
+        positions = step(&positions);
     }
+    recorder.finish()?;
+    Ok(())
 }
 
-fn display<T: std::io::Write>(f: &mut BufWriter<T>, positions: &[Pos], size: &Pos) {
-    let mut map = Map::<char> {
-        size: *size,
-        data: vec![vec![' '; size.x as usize]; size.y as usize],
-    };
+fn display(positions: &[Pos], size: &Pos) -> Image {
+    let mut map = TorusMap::new(*size, false);
     for pos in positions {
-        map.data[pos.y as usize][pos.x as usize] = '#';
-    }
-    for line in &map.data {
-        writeln!(f, "{}", line.iter().collect::<String>()).unwrap();
+        map[pos] = true;
     }
+    Image::from_map(
+        &map.into(),
+        |&robot| {
+            if robot {
+                Color::GREEN
+            } else {
+                Color::BLACK
+            }
+        },
+    )
+    .with_scale(2)
 }
 
 #[derive(Debug)]
@@ -89,7 +129,7 @@ impl Robot {
                     .map(|s| s.parse::<i64>())
                     .collect::<Result<Vec<i64>, _>>()?
                     .try_into()
-                    .unwrap();
+                    .map_err(|_| Error::msg("Expected four numbers"))?;
 
                 Ok(Self {
                     position: Pos { x: px, y: py },
@@ -99,3 +139,8 @@ impl Robot {
             .collect()
     }
 }
+
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    Robot::from_multi_str(input).map(|_| ())
+}
@@ -1,24 +1,117 @@
-use crate::utils::map::{ConvertibleFromChar, Map, Pos};
 use anyhow::{anyhow, Error};
 use itertools::Itertools;
 use regex::Regex;
 use std::fmt::{Debug, Formatter};
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::animate::Animation;
+use utils::image::{Color, Image};
+use utils::map::{ConvertibleFromChar, ConvertibleToChar, Map, Pos};
+use utils::rand::{maybe_generate, Rng};
+use utils::record::Recorder;
 
 pub mod utils;
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let (map, start, directions) = parse_input(&read_to_string(Path::new("data/input15.txt"))?)?;
+    let animate = std::env::args().any(|arg| arg == "--animate");
+    let mut recorder = std::env::args()
+        .skip_while(|arg| arg != "--record")
+        .nth(1)
+        .map(|path| Recorder::from_path(Path::new(&path), 5))
+        .transpose()?;
 
-    println!("{}", solve(map.clone(), start, &directions));
-    println!(
-        "{}",
-        solve2(map_to_two(&map), pos_to_two(&start), &directions)
+    let mut animation = Animation::new(frame_style);
+    let mut record_result = Ok(());
+    let result1 = solve(map.clone(), start, &directions, |_, _| {});
+    let result2 = solve2(
+        map_to_two(&map),
+        pos_to_two(&start),
+        &directions,
+        |map, pos| {
+            if animate {
+                animation.push(frame(map, pos));
+            }
+            if let (Some(recorder), Ok(())) = (&mut recorder, &record_result) {
+                let image = Image::from_map(&frame(map, pos), |c| frame_style(c).1);
+                record_result = recorder.push(&image.with_scale(4));
+            }
+        },
     );
+    record_result?;
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+    animation.play()?;
+
+    println!("{}\n{}", result1, result2);
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(4);
+    let mut grid: Vec<Vec<char>> = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    if x == 0 || y == 0 || x == size - 1 || y == size - 1 || rng.chance(0.05) {
+                        '#'
+                    } else if rng.chance(0.25) {
+                        'O'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let robot = (
+        rng.range(1..=size as i64 - 2),
+        rng.range(1..=size as i64 - 2),
+    );
+    grid[robot.1 as usize][robot.0 as usize] = '@';
+
+    let moves: Vec<char> = (0..10 * size)
+        .map(|_| *rng.choose(&['<', '>', '^', 'v']))
+        .collect();
+    let map: String = grid
+        .iter()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect();
+    map + "\n"
+        + &moves
+            .chunks(1000)
+            .map(|line| line.iter().collect::<String>() + "\n")
+            .collect::<String>()
+}
+
+fn frame<T: ConvertibleToChar>(map: &Map<T>, robot: &Pos) -> Map<char> {
+    let mut frame = Map {
+        size: map.size,
+        data: map
+            .data
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.to_char()).collect())
+            .collect(),
+    };
+    frame[robot] = '@';
+    frame
+}
+
+fn frame_style(c: &char) -> (char, Color) {
+    let color = match c {
+        '#' => Color::GRAY,
+        'O' | '[' | ']' => Color::YELLOW,
+        '@' => Color::RED,
+        _ => Color::new(60, 60, 60),
+    };
+    (*c, color)
+}
+
 fn pos_to_two(pos: &Pos) -> Pos {
     Pos {
         x: 2 * pos.x,
@@ -68,11 +161,18 @@ fn move_(map: &mut Map<Tile>, pos: &Pos, direction: &Direction) -> Pos {
     direction.move_(pos)
 }
 
-fn solve(mut map: Map<Tile>, mut pos: Pos, directions: &[Direction]) -> i64 {
+fn solve(
+    mut map: Map<Tile>,
+    mut pos: Pos,
+    directions: &[Direction],
+    mut on_step: impl FnMut(&Map<Tile>, &Pos),
+) -> i64 {
+    on_step(&map, &pos);
     for d in directions {
         if can_move(&map, &pos, d) {
             pos = move_(&mut map, &pos, d);
         }
+        on_step(&map, &pos);
     }
     score(&map)
 }
@@ -118,29 +218,26 @@ fn move2(map: &mut Map<TileTwo>, pos: &Pos, direction: &Direction) -> Pos {
     next
 }
 
-fn solve2(mut map: Map<TileTwo>, mut pos: Pos, directions: &[Direction]) -> i64 {
+fn solve2(
+    mut map: Map<TileTwo>,
+    mut pos: Pos,
+    directions: &[Direction],
+    mut on_step: impl FnMut(&Map<TileTwo>, &Pos),
+) -> i64 {
+    on_step(&map, &pos);
     for d in directions {
         if can_move2(&map, &pos, d) {
             pos = move2(&mut map, &pos, d);
         }
+        on_step(&map, &pos);
     }
 
     score(&map)
 }
 
 fn score<T: Score + Copy>(map: &Map<T>) -> i64 {
-    map.data
-        .iter()
-        .enumerate()
-        .flat_map(|(y, v)| {
-            v.iter().enumerate().map(move |(x, &tile)| {
-                if tile.count_for_score() {
-                    (100 * y + x) as i64
-                } else {
-                    0
-                }
-            })
-        })
+    map.positions_where(|&tile| tile.count_for_score())
+        .map(|pos| 100 * pos.y + pos.x)
         .sum()
 }
 
@@ -155,26 +252,17 @@ fn parse_input(input: &str) -> Result<(Map<Tile>, Pos, Vec<Direction>), Error> {
         .chars()
         .filter_map(|c| Direction::from_char(c).ok())
         .collect();
-    let start_pos = map_string
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars().enumerate().filter_map(move |(x, c)| {
-                if c == '@' {
-                    Some(Pos {
-                        x: x as i64,
-                        y: y as i64,
-                    })
-                } else {
-                    None
-                }
-            })
-        })
-        .next()
+    let start_pos = Map::<char>::from_str(map_string)?
+        .find(&'@')
         .ok_or(Error::msg("Robot not found"))?;
     Ok((map, start_pos, directions))
 }
 
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
+}
+
 impl ConvertibleFromChar for Tile {
     fn from_char(c: char) -> Result<Self, Error>
     where
@@ -220,29 +308,37 @@ impl Score for TileTwo {
     }
 }
 
-impl Debug for Tile {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let c = match self {
+impl ConvertibleToChar for Tile {
+    fn to_char(&self) -> char {
+        match self {
             Tile::Wall => '#',
             Tile::Floor => '.',
             Tile::Box => 'O',
-        };
-
-        write!(f, "{}", c)?;
-        Ok(())
+        }
     }
 }
 
-impl Debug for TileTwo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let c = match self {
+impl ConvertibleToChar for TileTwo {
+    fn to_char(&self) -> char {
+        match self {
             TileTwo::Wall => '#',
             TileTwo::Floor => '.',
             TileTwo::BoxLeft => '[',
             TileTwo::BoxRight => ']',
-        };
+        }
+    }
+}
 
-        write!(f, "{}", c)?;
+impl Debug for Tile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())?;
+        Ok(())
+    }
+}
+
+impl Debug for TileTwo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())?;
         Ok(())
     }
 }
@@ -1,22 +1,100 @@
-use crate::utils::map::{ConvertibleFromChar, Map, Pos};
 use anyhow::Error;
 use itertools::Itertools;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::image::{Color, Image};
+use utils::map::{ConvertibleFromChar, Map, Pos};
+use utils::rand::{maybe_generate, Rng};
+use utils::record::Recorder;
 
 pub mod utils;
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let (map, start, end) = parse_input(&read_to_string(Path::new("data/input16.txt"))?)?;
 
-    let (result1, result2) = solve(&map, start, end);
+    let mut recorder = std::env::args()
+        .skip_while(|arg| arg != "--record")
+        .nth(1)
+        .map(|path| Recorder::from_path(Path::new(&path), 5))
+        .transpose()?;
+
+    let mut record_result = Ok(());
+    let (result1, result2) = solve(&map, start, end, |step, seen| {
+        if let (Some(recorder), Ok(())) = (&mut recorder, &record_result) {
+            if step % 500 == 0 {
+                record_result = recorder.push(&search_frame(&map, seen));
+            }
+        }
+    });
+    record_result?;
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
     println!("{}\n{}", result1, result2);
     Ok(())
 }
 
-fn solve(map: &Map<Tile>, start: Pos, end: Pos) -> (i64, i64) {
+fn generate(rng: &mut Rng, size: usize) -> String {
+    // A maze carved by a randomized depth-first search over the odd cells, plus a few extra
+    // openings so there are several best paths.
+    let size = (size.max(5) - 1) | 1;
+    let mut grid = vec![vec!['#'; size]; size];
+    let mut stack = vec![(1, size - 2)];
+    grid[size - 2][1] = '.';
+    while let Some(&(x, y)) = stack.last() {
+        let mut options: Vec<(usize, usize)> = [(0, -2), (0, 2), (-2, 0), (2, 0)]
+            .iter()
+            .map(|(dx, dy)| ((x as i64 + dx) as usize, (y as i64 + dy) as usize))
+            .filter(|&(nx, ny)| 0 < nx && nx < size - 1 && 0 < ny && ny < size - 1)
+            .filter(|&(nx, ny)| grid[ny][nx] == '#')
+            .collect();
+        if options.is_empty() {
+            stack.pop();
+            continue;
+        }
+        rng.shuffle(&mut options);
+        let (nx, ny) = options[0];
+        grid[(y + ny) / 2][(x + nx) / 2] = '.';
+        grid[ny][nx] = '.';
+        stack.push((nx, ny));
+    }
+    for (y, row) in grid.iter_mut().enumerate().take(size - 1).skip(1) {
+        for (x, cell) in row.iter_mut().enumerate().take(size - 1).skip(1) {
+            if (x + y) % 2 == 1 && rng.chance(0.05) {
+                *cell = '.';
+            }
+        }
+    }
+    grid[size - 2][1] = 'S';
+    grid[1][size - 2] = 'E';
+    grid.iter()
+        .map(|line| line.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+fn search_frame(map: &Map<Tile>, seen: &HashMap<State, i64>) -> Image {
+    let mut image = Image::from_map(map, |tile| match tile {
+        Tile::Wall => Color::GRAY,
+        Tile::Floor => Color::BLACK,
+    })
+    .with_scale(2);
+    let visited = seen.keys().map(|state| state.pos).unique().collect_vec();
+    image.highlight(&visited, Color::BLUE);
+    image
+}
+
+fn solve(
+    map: &Map<Tile>,
+    start: Pos,
+    end: Pos,
+    mut on_step: impl FnMut(usize, &HashMap<State, i64>),
+) -> (i64, i64) {
     let mut heap = BinaryHeap::new();
     let start_state = State {
         pos: start,
@@ -28,8 +106,12 @@ fn solve(map: &Map<Tile>, start: Pos, end: Pos) -> (i64, i64) {
     });
     let mut seen = HashMap::new();
 
-    while let Some(scored_state) = heap.pop() {
+    for step in 0.. {
+        let Some(scored_state) = heap.pop() else {
+            break;
+        };
         seen.insert(scored_state.state, scored_state.score);
+        on_step(step, &seen);
         if scored_state.state.pos == end {
             let best_path_tiles = compute_best_path_tiles(&seen, end);
             return (scored_state.score, best_path_tiles);
@@ -115,33 +197,16 @@ fn compute_best_path_tiles(scores: &HashMap<State, i64>, end: Pos) -> i64 {
 
 fn parse_input(input: &str) -> Result<(Map<Tile>, Pos, Pos), Error> {
     let map = Map::from_str(input)?;
-    let mut start = None;
-    let mut end = None;
-    for (y, line) in input.lines().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
-            match ch {
-                'S' => {
-                    start = Some(Pos {
-                        x: x as i64,
-                        y: y as i64,
-                    })
-                }
-                'E' => {
-                    end = Some(Pos {
-                        x: x as i64,
-                        y: y as i64,
-                    })
-                }
-                _ => {}
-            }
-        }
-    }
+    let chars = Map::<char>::from_str(input)?;
+    let start = chars.find(&'S').ok_or(Error::msg("start tile missing"))?;
+    let end = chars.find(&'E').ok_or(Error::msg("end tile missing"))?;
+
+    Ok((map, start, end))
+}
 
-    Ok((
-        map,
-        start.expect("start tile missing"),
-        end.expect("end tile missing"),
-    ))
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    parse_input(input).map(|_| ())
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -1,9 +1,16 @@
-use anyhow::Error;
+pub mod utils;
+
+use anyhow::{anyhow, Error};
 use itertools::Itertools;
 use std::fs::read_to_string;
 use std::path::Path;
+use utils::rand::{maybe_generate, Rng};
 
 fn main() -> Result<(), Error> {
+    if maybe_generate(generate)? {
+        return Ok(());
+    }
+
     let (state, program) = parse_input(&read_to_string(Path::new("data/input17.txt"))?)?;
     let final_state = solve(&program, &state);
     println!(
@@ -14,16 +21,43 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    // Straight-line body, followed by `adv 3` and `jnz 0`: A shrinks every loop, so it halts.
+    let mut program: Vec<i64> = (0..size)
+        .flat_map(|_| {
+            let opcode = *rng.choose(&[1, 2, 4, 5, 6, 7]);
+            [opcode, rng.range(0..=if opcode == 1 { 7 } else { 6 })]
+        })
+        .collect();
+    if !program.chunks(2).any(|instruction| instruction[0] == 5) {
+        program.extend([5, 4]);
+    }
+    program.extend([0, 3, 3, 0]);
+    format!(
+        "Register A: {}\nRegister B: 0\nRegister C: 0\n\nProgram: {}\n",
+        rng.range(1..=1 << 40),
+        program.iter().join(",")
+    )
+}
+
 fn solve(program: &[i64], start_state: &State) -> State {
     let mut state = start_state.clone();
-    while 0 <= state.ip && state.ip < program.len() as i64 {
-        let ip = state.ip as usize;
-        get_instruction(program[ip])(program[ip + 1], &mut state);
-        state.ip += 2;
-    }
+    while step(program, &mut state) {}
     state
 }
 
+// Runs one instruction; false once the program has halted by reading an opcode or its operand
+// past the end.
+fn step(program: &[i64], state: &mut State) -> bool {
+    if state.ip < 0 || state.ip + 1 >= program.len() as i64 {
+        return false;
+    }
+    let ip = state.ip as usize;
+    get_instruction(program[ip])(program[ip + 1], state);
+    state.ip += 2;
+    true
+}
+
 fn solve2(program: &[i64], start_state: &State) -> i64 {
     let mut result = 0;
     for i in 0..(program.len() / 2) {
@@ -35,7 +69,7 @@ fn solve2(program: &[i64], start_state: &State) -> i64 {
                 state
             };
             let output = solve(program, &state).output;
-            if output.len() % 2 != 0 {
+            if !output.len().is_multiple_of(2) {
                 continue;
             }
             if output[0] == program[program.len() - 2 * i - 2]
@@ -59,8 +93,16 @@ fn combo(operand: i64, state: &State) -> i64 {
     }
 }
 
+// A divided by 2 to the power of the combo operand; large shifts leave nothing.
+fn divide_a(operand: i64, state: &State) -> i64 {
+    u32::try_from(combo(operand, state))
+        .ok()
+        .and_then(|shift| state.a.checked_shr(shift))
+        .unwrap_or(0)
+}
+
 fn adv(operand: i64, state: &mut State) {
-    state.a >>= combo(operand, state);
+    state.a = divide_a(operand, state);
 }
 
 fn bxl(operand: i64, state: &mut State) {
@@ -85,10 +127,10 @@ fn out(operand: i64, state: &mut State) {
     // print!("{},", output);
 }
 fn bdv(operand: i64, state: &mut State) {
-    state.b = state.a >> combo(operand, state);
+    state.b = divide_a(operand, state);
 }
 fn cdv(operand: i64, state: &mut State) {
-    state.c = state.a >> combo(operand, state);
+    state.c = divide_a(operand, state);
 }
 
 fn get_instruction(opcode: i64) -> fn(i64, &mut State) {
@@ -130,6 +172,27 @@ fn parse_input(input: &str) -> Result<(State, Vec<i64>), Error> {
         .split(',')
         .map(|x| x.parse::<i64>())
         .collect::<Result<Vec<_>, _>>()?;
+    if !program.len().is_multiple_of(2) {
+        return Err(Error::msg("Program has an opcode without operand"));
+    }
+    for (i, instruction) in program.chunks(2).enumerate() {
+        let [opcode, operand] = [instruction[0], instruction[1]];
+        if !(0..=7).contains(&opcode) || !(0..=7).contains(&operand) {
+            return Err(anyhow!("instruction {}: values must be 3-bit numbers", i));
+        }
+        // adv, bst, out, bdv and cdv take a combo operand, where 7 is reserved.
+        if [0, 2, 5, 6, 7].contains(&opcode) && operand == 7 {
+            return Err(anyhow!("instruction {}: invalid combo operand 7", i));
+        }
+        // Odd targets would run operands as opcodes, which aren't validated as such.
+        if opcode == 3 && operand % 2 == 1 {
+            return Err(anyhow!(
+                "instruction {}: jump to odd address {}",
+                i,
+                operand
+            ));
+        }
+    }
 
     Ok((
         State::new(registers[0], registers[1], registers[2]),
@@ -137,6 +200,18 @@ fn parse_input(input: &str) -> Result<(State, Vec<i64>), Error> {
     ))
 }
 
+#[cfg(fuzzing)]
+pub fn fuzz_parse(input: &str) -> Result<(), Error> {
+    // Also run a valid program for a while, as not every program halts.
+    let (mut state, program) = parse_input(input)?;
+    for _ in 0..10_000 {
+        if !step(&program, &mut state) {
+            break;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 struct State {
     a: i64,
@@ -184,4 +259,37 @@ mod tests {
         assert_eq!(final_state.output, vec![4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0]);
         assert_eq!(final_state.a, 0);
     }
+
+    #[test]
+    fn large_shifts() {
+        let state = State::new(1 << 40, 0, 0);
+        assert_eq!(solve(&[7, 4], &state).c, 0);
+        assert_eq!(solve(&[6, 3], &state).b, 1 << 37);
+    }
+
+    #[test]
+    fn generated_programs_halt() {
+        for seed in 0..20 {
+            let (state, program) = parse_input(&generate(&mut Rng::new(seed), 8)).unwrap();
+            assert!(!solve(&program, &state).output.is_empty());
+        }
+    }
+
+    #[test]
+    fn invalid_programs() {
+        let input = |program: &str| {
+            parse_input(&format!(
+                "Register A: 1\nRegister B: 0\nRegister C: 0\n\nProgram: {}\n",
+                program
+            ))
+        };
+        assert!(input("0,1,5,4,3,0").is_ok());
+        assert!(input("1,7").is_ok());
+        for program in ["0,1,5", "8,0", "1,8", "0,7", "5,7", "-1,0", "0,2,7,4,3,1"] {
+            assert!(input(program).is_err(), "{}", program);
+        }
+        // Jumping to the last operand halts instead of reading past the end.
+        let state = State::new(1, 0, 0);
+        assert_eq!(solve(&[3, 3, 1, 0], &state).ip, 3);
+    }
 }
@@ -0,0 +1,93 @@
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub word: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Multi-pattern matcher over chars; `outputs` of a node also include the words of its fail chain.
+pub struct AhoCorasick {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    outputs: Vec<Vec<usize>>,
+    lengths: Vec<usize>,
+}
+
+impl AhoCorasick {
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut automaton = Self {
+            goto: vec![HashMap::new()],
+            fail: vec![0],
+            outputs: vec![vec![]],
+            lengths: vec![],
+        };
+        for (word, chars) in words.iter().enumerate() {
+            let mut node = 0;
+            for c in chars.as_ref().chars() {
+                node = match automaton.goto[node].get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(0);
+                        automaton.outputs.push(vec![]);
+                        let next = automaton.goto.len() - 1;
+                        automaton.goto[node].insert(c, next);
+                        next
+                    }
+                };
+            }
+            // Empty words would match everywhere, so they are never reported.
+            if node != 0 {
+                automaton.outputs[node].push(word);
+            }
+            automaton.lengths.push(chars.as_ref().chars().count());
+        }
+
+        let mut queue: VecDeque<usize> = automaton.goto[0].values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> = automaton.goto[node]
+                .iter()
+                .map(|(&c, &child)| (c, child))
+                .collect();
+            for (c, child) in children {
+                let fail = automaton.step(automaton.fail[node], c);
+                automaton.fail[child] = fail;
+                let inherited = automaton.outputs[fail].clone();
+                automaton.outputs[child].extend(inherited);
+                queue.push_back(child);
+            }
+        }
+        automaton
+    }
+
+    fn step(&self, mut node: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.goto[node].get(&c) {
+                return next;
+            }
+            if node == 0 {
+                return 0;
+            }
+            node = self.fail[node];
+        }
+    }
+
+    // All matches, overlapping ones included, as char indices into `text`.
+    pub fn find_overlapping(&self, text: impl IntoIterator<Item = char>) -> Vec<Match> {
+        let mut node = 0;
+        let mut matches = vec![];
+        for (i, c) in text.into_iter().enumerate() {
+            node = self.step(node, c);
+            for &word in &self.outputs[node] {
+                matches.push(Match {
+                    word,
+                    start: i + 1 - self.lengths[word],
+                    end: i + 1,
+                });
+            }
+        }
+        matches
+    }
+}
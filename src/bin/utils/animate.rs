@@ -0,0 +1,247 @@
+use super::diff::Change;
+use super::image::Color;
+use super::map::Map;
+use anyhow::Error;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+enum Command {
+    TogglePause,
+    Next,
+    Previous,
+    Faster,
+    Slower,
+    Quit,
+}
+
+impl Command {
+    fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "" | "p" => Some(Command::TogglePause),
+            "n" => Some(Command::Next),
+            "b" => Some(Command::Previous),
+            "+" => Some(Command::Faster),
+            "-" => Some(Command::Slower),
+            "q" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+pub fn ansi<T>(map: &Map<T>, style: impl Fn(&T) -> (char, Color)) -> String {
+    let mut result = String::new();
+    for row in &map.data {
+        let mut current = None;
+        for value in row {
+            let (c, color) = style(value);
+            if current != Some(color) {
+                result += &format!("\x1b[38;2;{};{};{}m", color.r, color.g, color.b);
+                current = Some(color);
+            }
+            result.push(c);
+        }
+        result += "\x1b[0m\n";
+    }
+    result
+}
+
+// Keeps the first and last frame plus the cells changed by each step, so long runs stay small.
+pub struct Animation<T, S: Fn(&T) -> (char, Color)> {
+    first: Option<Map<T>>,
+    last: Option<Map<T>>,
+    steps: Vec<Vec<Change<T>>>,
+    pub delay: Duration,
+    style: S,
+}
+
+impl<T: Copy + PartialEq, S: Fn(&T) -> (char, Color)> Animation<T, S> {
+    pub fn new(style: S) -> Self {
+        Self {
+            first: None,
+            last: None,
+            steps: vec![],
+            delay: Duration::from_millis(100),
+            style,
+        }
+    }
+
+    pub fn with_fps(mut self, fps: f64) -> Self {
+        self.delay = Duration::try_from_secs_f64(1.0 / fps)
+            .unwrap_or(MAX_DELAY)
+            .clamp(MIN_DELAY, MAX_DELAY);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.first.as_ref().map_or(0, |_| self.steps.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_none()
+    }
+
+    pub fn push(&mut self, frame: Map<T>) {
+        match &mut self.last {
+            None => {
+                self.first = Some(frame.clone());
+                self.last = Some(frame);
+            }
+            Some(last) => {
+                self.steps.push(last.diff(&frame));
+                *last = frame;
+            }
+        }
+    }
+
+    // Turns `current`, showing frame `from`, into frame `to`.
+    fn seek(&self, current: &mut Map<T>, from: usize, to: usize) {
+        if to > from {
+            for change in self.steps[from..to].iter().flatten() {
+                current[&change.pos] = change.new;
+            }
+        } else {
+            // Undo the latest step first, so cells changed more than once end up at their oldest value.
+            for change in self.steps[to..from].iter().rev().flatten() {
+                current[&change.pos] = change.old;
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        frame: &Map<T>,
+        index: usize,
+        delay: Duration,
+        paused: bool,
+    ) -> Result<(), Error> {
+        let mut out = stdout().lock();
+        write!(out, "\x1b[H\x1b[2J{}", ansi(frame, &self.style))?;
+        writeln!(
+            out,
+            "frame {}/{}  {:.1} fps{}  [enter/p] pause  [n]ext  [b]ack  [+/-] speed  [q]uit",
+            index + 1,
+            self.len(),
+            1.0 / delay.as_secs_f64(),
+            if paused { "  (paused)" } else { "" }
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+
+    pub fn play(&self) -> Result<(), Error> {
+        let Some(mut frame) = self.first.clone() else {
+            return Ok(());
+        };
+        let commands = spawn_command_reader();
+        let mut delay = self.delay;
+        let mut paused = false;
+        let mut index = 0;
+        loop {
+            self.draw(&frame, index, delay, paused)?;
+            let received = if paused {
+                commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                commands.recv_timeout(delay)
+            };
+            let command = match received {
+                Ok(command) => command,
+                Err(RecvTimeoutError::Disconnected) if paused => return Ok(()),
+                Err(error) => {
+                    // Without input (e.g. stdin is not a terminal) keep playing at the set speed.
+                    if error == RecvTimeoutError::Disconnected {
+                        thread::sleep(delay);
+                    }
+                    if index + 1 == self.len() {
+                        return Ok(());
+                    }
+                    self.seek(&mut frame, index, index + 1);
+                    index += 1;
+                    continue;
+                }
+            };
+            match command {
+                Command::Quit => return Ok(()),
+                Command::TogglePause => paused = !paused,
+                Command::Next => {
+                    paused = true;
+                    let next = (index + 1).min(self.len() - 1);
+                    self.seek(&mut frame, index, next);
+                    index = next;
+                }
+                Command::Previous => {
+                    paused = true;
+                    let previous = index.saturating_sub(1);
+                    self.seek(&mut frame, index, previous);
+                    index = previous;
+                }
+                Command::Faster => delay = (delay / 2).max(MIN_DELAY),
+                Command::Slower => delay = (delay * 2).min(MAX_DELAY),
+            }
+        }
+    }
+}
+
+fn spawn_command_reader() -> Receiver<Command> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        for line in stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if let Some(command) = Command::from_str(&line) {
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation() -> Animation<char, impl Fn(&char) -> (char, Color)> {
+        Animation::new(|&c| (c, Color::WHITE))
+    }
+
+    #[test]
+    fn delay_stays_in_range() {
+        assert_eq!(animation().with_fps(0.0).delay, MAX_DELAY);
+        assert_eq!(animation().with_fps(-3.0).delay, MAX_DELAY);
+        assert_eq!(animation().with_fps(f64::NAN).delay, MAX_DELAY);
+        assert_eq!(animation().with_fps(1e12).delay, MIN_DELAY);
+        assert_eq!(animation().with_fps(4.0).delay, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn frames_replay_from_steps() {
+        let frames: Vec<Map<char>> = ["@..\n", ".@.\n", ".@.\n", "..@\n", "@.@\n", ".@.\n"]
+            .iter()
+            .map(|s| Map::from_str(s).unwrap())
+            .collect();
+        let mut animation = animation();
+        assert!(animation.is_empty());
+        for frame in &frames {
+            animation.push(frame.clone());
+        }
+        assert_eq!(animation.len(), 6);
+        assert_eq!(animation.steps[1], vec![]);
+
+        for from in 0..frames.len() {
+            for to in 0..frames.len() {
+                let mut current = frames[from].clone();
+                animation.seek(&mut current, from, to);
+                assert_eq!(
+                    current.data, frames[to].data,
+                    "seek from {} to {}",
+                    from, to
+                );
+            }
+        }
+    }
+}
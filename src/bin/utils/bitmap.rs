@@ -0,0 +1,219 @@
+use super::map::Pos;
+
+const WORD_BITS: usize = u64::BITS as usize;
+const DIRECTIONS: usize = 4;
+
+fn word_count(num_bits: usize) -> usize {
+    num_bits.div_ceil(WORD_BITS)
+}
+
+fn cell_index(size: &Pos, pos: &Pos) -> usize {
+    assert!(
+        0 <= pos.x && pos.x < size.x && 0 <= pos.y && pos.y < size.y,
+        "position {:?} outside of bit map",
+        pos
+    );
+    (pos.y * size.x + pos.x) as usize
+}
+
+fn cell_pos(size: &Pos, index: usize) -> Pos {
+    Pos {
+        x: index as i64 % size.x,
+        y: index as i64 / size.x,
+    }
+}
+
+#[derive(Clone)]
+pub struct BitMap {
+    pub size: Pos,
+    words: Vec<u64>,
+}
+
+impl BitMap {
+    pub fn new(size: Pos) -> Self {
+        Self {
+            size,
+            words: vec![0; word_count((size.x * size.y) as usize)],
+        }
+    }
+
+    pub fn contains(&self, pos: &Pos) -> bool {
+        let i = cell_index(&self.size, pos);
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    pub fn insert(&mut self, pos: &Pos) -> bool {
+        let i = cell_index(&self.size, pos);
+        let word = &mut self.words[i / WORD_BITS];
+        let mask = 1 << (i % WORD_BITS);
+        let inserted = *word & mask == 0;
+        *word |= mask;
+        inserted
+    }
+
+    pub fn remove(&mut self, pos: &Pos) -> bool {
+        let i = cell_index(&self.size, pos);
+        let word = &mut self.words[i / WORD_BITS];
+        let mask = 1 << (i % WORD_BITS);
+        let removed = *word & mask != 0;
+        *word &= !mask;
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.words.iter().enumerate().flat_map(move |(w, &word)| {
+            (0..WORD_BITS)
+                .filter(move |b| word & (1 << b) != 0)
+                .map(move |b| cell_pos(&self.size, w * WORD_BITS + b))
+        })
+    }
+}
+
+// Stores a 4-bit direction mask per cell, for tracking (position, direction) states.
+#[derive(Clone)]
+pub struct DirectionBitMap {
+    pub size: Pos,
+    words: Vec<u64>,
+}
+
+impl DirectionBitMap {
+    pub fn new(size: Pos) -> Self {
+        Self {
+            size,
+            words: vec![0; word_count((size.x * size.y) as usize * DIRECTIONS)],
+        }
+    }
+
+    fn bit(&self, pos: &Pos, direction: usize) -> usize {
+        assert!(direction < DIRECTIONS, "direction index out of range");
+        cell_index(&self.size, pos) * DIRECTIONS + direction
+    }
+
+    pub fn mask(&self, pos: &Pos) -> u8 {
+        let i = cell_index(&self.size, pos) * DIRECTIONS;
+        ((self.words[i / WORD_BITS] >> (i % WORD_BITS)) & 0b1111) as u8
+    }
+
+    pub fn visited(&self, pos: &Pos) -> bool {
+        self.mask(pos) != 0
+    }
+
+    pub fn contains(&self, pos: &Pos, direction: usize) -> bool {
+        let i = self.bit(pos, direction);
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    pub fn insert(&mut self, pos: &Pos, direction: usize) -> bool {
+        let i = self.bit(pos, direction);
+        let word = &mut self.words[i / WORD_BITS];
+        let mask = 1 << (i % WORD_BITS);
+        let inserted = *word & mask == 0;
+        *word |= mask;
+        inserted
+    }
+
+    pub fn remove(&mut self, pos: &Pos, direction: usize) -> bool {
+        let i = self.bit(pos, direction);
+        let word = &mut self.words[i / WORD_BITS];
+        let mask = 1 << (i % WORD_BITS);
+        let removed = *word & mask != 0;
+        *word &= !mask;
+        removed
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..(self.size.x * self.size.y) as usize)
+            .map(|i| cell_pos(&self.size, i))
+            .filter(|pos| self.visited(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rand::Rng;
+    use super::*;
+    use std::collections::HashSet;
+
+    // 13 * 11 = 143 cells, so the bits span three words.
+    const SIZE: Pos = Pos { x: 13, y: 11 };
+
+    #[test]
+    fn bits_at_word_boundaries() {
+        let mut map = BitMap::new(SIZE);
+        for i in [0, 63, 64, 127, 128, 142] {
+            assert!(map.insert(&cell_pos(&SIZE, i)));
+        }
+        assert!(!map.insert(&Pos { x: 11, y: 4 }));
+        assert_eq!(map.words, vec![1 | 1 << 63, 1 | 1 << 63, 1 | 1 << 14]);
+        assert_eq!(
+            map.iter()
+                .map(|pos| cell_index(&SIZE, &pos))
+                .collect::<Vec<_>>(),
+            vec![0, 63, 64, 127, 128, 142]
+        );
+    }
+
+    #[test]
+    fn matches_hash_set() {
+        let mut rng = Rng::new(28);
+        let mut map = BitMap::new(SIZE);
+        let mut set = HashSet::new();
+        for _ in 0..2000 {
+            let pos = Pos {
+                x: rng.range(0..=SIZE.x - 1),
+                y: rng.range(0..=SIZE.y - 1),
+            };
+            if rng.chance(0.6) {
+                assert_eq!(map.insert(&pos), set.insert(pos));
+            } else {
+                assert_eq!(map.remove(&pos), set.remove(&pos));
+            }
+            assert_eq!(map.contains(&pos), set.contains(&pos));
+        }
+        assert_eq!(map.count(), set.len());
+        assert_eq!(map.iter().collect::<HashSet<_>>(), set);
+        map.clear();
+        assert_eq!(map.count(), 0);
+    }
+
+    #[test]
+    fn directions_per_cell() {
+        let mut map = DirectionBitMap::new(SIZE);
+        // Cells 15 and 16 sit on either side of the first word boundary.
+        let (last, first) = (cell_pos(&SIZE, 15), cell_pos(&SIZE, 16));
+        assert!(map.insert(&last, 3));
+        assert!(map.insert(&first, 0));
+        assert!(!map.insert(&first, 0));
+        assert!(map.insert(&first, 2));
+        assert_eq!(map.words[0], 1 << 63);
+        assert_eq!(map.mask(&last), 0b1000);
+        assert_eq!(map.mask(&first), 0b0101);
+        assert!(map.contains(&first, 2) && !map.contains(&first, 1));
+        assert!(map.remove(&first, 0));
+        assert_eq!(map.count(), 2);
+        assert_eq!(map.positions().collect::<Vec<_>>(), vec![last, first]);
+        assert!(!map.visited(&Pos { x: 0, y: 0 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of bit map")]
+    fn outside_panics() {
+        BitMap::new(SIZE).contains(&Pos { x: 13, y: 0 });
+    }
+}
@@ -0,0 +1,128 @@
+use std::cmp::Reverse;
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counter<K: Eq + Hash> {
+    counts: HashMap<K, usize>,
+}
+
+impl<K: Eq + Hash> Counter<K> {
+    pub fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn from_keys(keys: impl IntoIterator<Item = K>) -> Self {
+        keys.into_iter().map(|key| (key, 1)).collect()
+    }
+
+    pub fn add(&mut self, key: K) {
+        self.add_count(key, 1);
+    }
+
+    pub fn add_count(&mut self, key: K, count: usize) {
+        *self.counts.entry(key).or_insert(0) += count;
+    }
+
+    pub fn get(&self, key: &K) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    pub fn merge(&mut self, other: Counter<K>) {
+        self.extend(other);
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, usize)> {
+        self.counts.iter().map(|(key, &count)| (key, count))
+    }
+
+    pub fn most_common(&self, n: usize) -> Vec<(&K, usize)> {
+        let mut counts: Vec<_> = self.iter().collect();
+        counts.sort_by_key(|&(_, count)| Reverse(count));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl<K: Eq + Hash> Default for Counter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash> Extend<(K, usize)> for Counter<K> {
+    fn extend<I: IntoIterator<Item = (K, usize)>>(&mut self, iter: I) {
+        for (key, count) in iter {
+            self.add_count(key, count);
+        }
+    }
+}
+
+impl<K: Eq + Hash> FromIterator<(K, usize)> for Counter<K> {
+    fn from_iter<I: IntoIterator<Item = (K, usize)>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<K: Eq + Hash> IntoIterator for Counter<K> {
+    type Item = (K, usize);
+    type IntoIter = hash_map::IntoIter<K, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.counts.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting() {
+        let mut counter = Counter::from_keys("mississippi".chars());
+        assert_eq!(counter.get(&'s'), 4);
+        assert_eq!(counter.get(&'x'), 0);
+        assert_eq!(counter.len(), 4);
+        assert_eq!(counter.total(), 11);
+
+        counter.add('s');
+        counter.add_count('p', 4);
+        assert_eq!(
+            counter.most_common(3),
+            vec![(&'p', 6), (&'s', 5), (&'i', 4)]
+        );
+        assert_eq!(counter.most_common(10).len(), 4);
+    }
+
+    #[test]
+    fn merging() {
+        let mut counter: Counter<&str> = [("a", 2), ("b", 1), ("a", 1)].into_iter().collect();
+        assert_eq!(counter.get(&"a"), 3);
+        counter.merge(Counter::from_keys(["b", "c"]));
+        let mut counts: Vec<_> = counter.into_iter().collect();
+        counts.sort();
+        assert_eq!(counts, vec![("a", 3), ("b", 2), ("c", 1)]);
+
+        let empty = Counter::<char>::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.total(), 0);
+        assert!(empty.most_common(1).is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// The states at steps `prefix + i` and `prefix + i + length` are equal for every i >= 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub prefix: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.length
+        }
+    }
+
+    pub fn state_at<S: Clone>(&self, start: &S, step: impl Fn(&S) -> S, n: usize) -> S {
+        (0..self.reduce(n)).fold(start.clone(), |state, _| step(&state))
+    }
+}
+
+// All finders below loop forever if the sequence of states never repeats.
+
+pub fn floyd<S: PartialEq + Clone>(start: &S, step: impl Fn(&S) -> S) -> Cycle {
+    let mut tortoise = step(start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    let mut prefix = 0;
+    tortoise = start.clone();
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+
+    let mut length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+    Cycle { prefix, length }
+}
+
+pub fn brent<S: PartialEq + Clone>(start: &S, step: impl Fn(&S) -> S) -> Cycle {
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut tortoise = start.clone();
+    let mut hare = (0..length).fold(start.clone(), |state, _| step(&state));
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    Cycle { prefix, length }
+}
+
+pub fn find_cycle<S: Eq + Hash + Clone>(start: &S, step: impl Fn(&S) -> S) -> Cycle {
+    let mut seen = HashMap::new();
+    let mut state = start.clone();
+    for i in 0.. {
+        if let Some(first) = seen.insert(state.clone(), i) {
+            return Cycle {
+                prefix: first,
+                length: i - first,
+            };
+        }
+        state = step(&state);
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x -> x^2 + 1 mod 255 from 3 runs 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+    fn step(x: &u64) -> u64 {
+        (x * x + 1) % 255
+    }
+
+    fn brute_force(start: u64, step: impl Fn(&u64) -> u64) -> Cycle {
+        let mut states = vec![start];
+        loop {
+            let next = step(states.last().unwrap());
+            if let Some(prefix) = states.iter().position(|&state| state == next) {
+                return Cycle {
+                    prefix,
+                    length: states.len() - prefix,
+                };
+            }
+            states.push(next);
+        }
+    }
+
+    #[test]
+    fn finders_agree() {
+        assert_eq!(
+            find_cycle(&3, step),
+            Cycle {
+                prefix: 2,
+                length: 6
+            }
+        );
+        for start in 0..255 {
+            let expected = brute_force(start, step);
+            assert_eq!(floyd(&start, step), expected, "floyd from {}", start);
+            assert_eq!(brent(&start, step), expected, "brent from {}", start);
+            assert_eq!(find_cycle(&start, step), expected);
+        }
+    }
+
+    #[test]
+    fn pure_cycles_and_fixed_points() {
+        let rotate = |x: &u64| (x + 1) % 7;
+        let expected = Cycle {
+            prefix: 0,
+            length: 7,
+        };
+        assert_eq!(floyd(&4, rotate), expected);
+        assert_eq!(brent(&4, rotate), expected);
+        let fixed = Cycle {
+            prefix: 0,
+            length: 1,
+        };
+        assert_eq!(brent(&0, |x: &u64| *x), fixed);
+        assert_eq!(floyd(&0, |x: &u64| *x), fixed);
+    }
+
+    #[test]
+    fn skipping_ahead() {
+        let cycle = find_cycle(&3, step);
+        assert_eq!(cycle.reduce(1), 1);
+        assert_eq!(cycle.reduce(8), 2);
+        assert_eq!(cycle.reduce(1_000_000_000), 2 + (1_000_000_000 - 2) % 6);
+        let slow = (0..1000).fold(3, |x, _| step(&x));
+        assert_eq!(cycle.state_at(&3, step, 1000), slow);
+    }
+}
@@ -0,0 +1,151 @@
+use super::map::{Map, Pos};
+use std::collections::HashMap;
+use std::ops::{Deref, Index, IndexMut};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    pub pos: Pos,
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: Copy + PartialEq> Map<T> {
+    pub fn diff(&self, other: &Map<T>) -> Vec<Change<T>> {
+        assert_eq!(self.size, other.size, "can only diff maps of equal size");
+        self.iter_pos()
+            .filter(|&(pos, old)| other[&pos] != *old)
+            .map(|(pos, &old)| Change {
+                pos,
+                old,
+                new: other[&pos],
+            })
+            .collect()
+    }
+}
+
+pub struct TrackedMap<T> {
+    map: Map<T>,
+    pending: HashMap<Pos, T>,
+    steps: Vec<Vec<Change<T>>>,
+}
+
+impl<T: Copy + PartialEq> TrackedMap<T> {
+    pub fn new(map: Map<T>) -> Self {
+        Self {
+            map,
+            pending: HashMap::new(),
+            steps: vec![],
+        }
+    }
+
+    pub fn end_step(&mut self) -> &[Change<T>] {
+        let mut changes: Vec<_> = self
+            .pending
+            .drain()
+            .filter(|(pos, old)| self.map[pos] != *old)
+            .map(|(pos, old)| Change {
+                pos,
+                old,
+                new: self.map[&pos],
+            })
+            .collect();
+        changes.sort_by_key(|change| (change.pos.y, change.pos.x));
+        self.steps.push(changes);
+        self.steps.last().unwrap()
+    }
+
+    pub fn steps(&self) -> &[Vec<Change<T>>] {
+        &self.steps
+    }
+
+    pub fn into_inner(self) -> Map<T> {
+        self.map
+    }
+}
+
+impl<T> Deref for TrackedMap<T> {
+    type Target = Map<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<T> Index<&Pos> for TrackedMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: &Pos) -> &Self::Output {
+        &self.map[pos]
+    }
+}
+
+impl<T: Copy> IndexMut<&Pos> for TrackedMap<T> {
+    fn index_mut(&mut self, pos: &Pos) -> &mut Self::Output {
+        self.pending.entry(*pos).or_insert(self.map[pos]);
+        &mut self.map[pos]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(s: &str) -> Map<char> {
+        Map::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn diff_lists_changed_cells() {
+        let before = map("ab\ncd\n");
+        let after = map("ax\ncy\n");
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                Change {
+                    pos: Pos { x: 1, y: 0 },
+                    old: 'b',
+                    new: 'x'
+                },
+                Change {
+                    pos: Pos { x: 1, y: 1 },
+                    old: 'd',
+                    new: 'y'
+                }
+            ]
+        );
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn steps_record_net_changes() {
+        let mut tracked = TrackedMap::new(map("...\n"));
+        tracked[&Pos { x: 2, y: 0 }] = 'O';
+        tracked[&Pos { x: 0, y: 0 }] = '@';
+        tracked[&Pos { x: 0, y: 0 }] = 'O';
+        assert_eq!(
+            tracked.end_step(),
+            &[
+                Change {
+                    pos: Pos { x: 0, y: 0 },
+                    old: '.',
+                    new: 'O'
+                },
+                Change {
+                    pos: Pos { x: 2, y: 0 },
+                    old: '.',
+                    new: 'O'
+                }
+            ]
+        );
+
+        // Writing a cell back to its value within a step is no change at all.
+        tracked[&Pos { x: 1, y: 0 }] = '#';
+        tracked[&Pos { x: 1, y: 0 }] = '.';
+        assert!(tracked.end_step().is_empty());
+
+        assert_eq!(tracked.steps().len(), 2);
+        assert_eq!(tracked[&Pos { x: 2, y: 0 }], 'O');
+        assert_eq!(tracked.size, Pos { x: 3, y: 1 });
+        assert_eq!(tracked.into_inner().data, vec![vec!['O', '.', 'O']]);
+    }
+}
@@ -0,0 +1,165 @@
+use super::map::ConvertibleFromChar;
+use super::search::Neighbors;
+use anyhow::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Index, IndexMut, Mul};
+
+// Axial coordinates; the third cube coordinate is s = -q - r.
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
+pub struct Hex {
+    pub q: i64,
+    pub r: i64,
+}
+
+impl Hex {
+    pub const DIRECTIONS: [Hex; 6] = [
+        Hex { q: 1, r: 0 },
+        Hex { q: 1, r: -1 },
+        Hex { q: 0, r: -1 },
+        Hex { q: -1, r: 0 },
+        Hex { q: -1, r: 1 },
+        Hex { q: 0, r: 1 },
+    ];
+
+    pub fn s(&self) -> i64 {
+        -self.q - self.r
+    }
+
+    pub fn distance(&self, other: &Hex) -> i64 {
+        let diff = Hex {
+            q: self.q - other.q,
+            r: self.r - other.r,
+        };
+        diff.q.abs().max(diff.r.abs()).max(diff.s().abs())
+    }
+
+    pub fn adjacent(&self) -> [Hex; 6] {
+        Self::DIRECTIONS.map(|direction| *self + direction)
+    }
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+
+impl Mul<i64> for Hex {
+    type Output = Hex;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self {
+            q: self.q * rhs,
+            r: self.r * rhs,
+        }
+    }
+}
+
+// Stored as a rhombus: line r of the input holds the cells with q = 0, 1, ...
+#[derive(Clone)]
+pub struct HexMap<T> {
+    pub size: Hex,
+    pub data: Vec<Vec<T>>,
+}
+
+impl<T: 'static + ConvertibleFromChar + Copy> HexMap<T> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        let data: Vec<Vec<T>> = s
+            .lines()
+            .map(|l| l.chars().filter(|c| *c != ' ').map(T::from_char).collect())
+            .collect::<Result<Vec<_>, _>>()?;
+        let size = Hex {
+            q: data.first().ok_or(Error::msg("Empty data"))?.len() as i64,
+            r: data.len() as i64,
+        };
+        if size.q == 0 || data.iter().any(|row| row.len() as i64 != size.q) {
+            return Err(Error::msg("Rows have different lengths"));
+        }
+        Ok(Self { data, size })
+    }
+}
+
+impl<T> HexMap<T> {
+    pub fn contains(&self, pos: &Hex) -> bool {
+        0 <= pos.q && pos.q < self.size.q && 0 <= pos.r && pos.r < self.size.r
+    }
+
+    pub fn neighbors(&self, pos: &Hex) -> Vec<Hex> {
+        pos.adjacent()
+            .into_iter()
+            .filter(|pos| self.contains(pos))
+            .collect()
+    }
+}
+
+impl<T> Neighbors for HexMap<T> {
+    type Pos = Hex;
+
+    fn neighbors(&self, pos: &Hex) -> Vec<Hex> {
+        HexMap::neighbors(self, pos)
+    }
+}
+
+impl<T> Index<&Hex> for HexMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: &Hex) -> &Self::Output {
+        &self.data[pos.r as usize][pos.q as usize]
+    }
+}
+
+impl<T> IndexMut<&Hex> for HexMap<T> {
+    fn index_mut(&mut self, pos: &Hex) -> &mut Self::Output {
+        &mut self.data[pos.r as usize][pos.q as usize]
+    }
+}
+
+impl<T: std::fmt::Debug> Display for HexMap<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (r, line) in self.data.iter().enumerate() {
+            writeln!(f, "{}{:?}", " ".repeat(r), line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let origin = Hex { q: 0, r: 0 };
+        for direction in Hex::DIRECTIONS {
+            assert_eq!(origin.distance(&direction), 1);
+            assert_eq!(direction.q + direction.r + direction.s(), 0);
+        }
+        assert_eq!(origin.distance(&(Hex::DIRECTIONS[1] * 3)), 3);
+        assert_eq!(Hex { q: 2, r: -1 }.distance(&Hex { q: -1, r: 3 }), 4);
+    }
+
+    #[test]
+    fn rhombus_map() {
+        let map: HexMap<char> = HexMap::from_str("abc\n def\n  ghi\n").unwrap();
+        assert_eq!(map.size, Hex { q: 3, r: 3 });
+        assert_eq!(map[&Hex { q: 1, r: 2 }], 'h');
+        assert_eq!(map.neighbors(&Hex { q: 1, r: 1 }).len(), 6);
+        let mut corner = map.neighbors(&Hex { q: 0, r: 0 });
+        corner.sort_by_key(|hex| (hex.r, hex.q));
+        assert_eq!(corner, vec![Hex { q: 1, r: 0 }, Hex { q: 0, r: 1 }]);
+        assert_eq!(map.neighbors(&Hex { q: 2, r: 0 }).len(), 3);
+    }
+
+    #[test]
+    fn ragged_rows() {
+        assert!(HexMap::<char>::from_str("abc\n de\n").is_err());
+        assert!(HexMap::<char>::from_str("\n\n").is_err());
+        assert!(HexMap::<char>::from_str("").is_err());
+    }
+}
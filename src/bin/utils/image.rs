@@ -0,0 +1,405 @@
+use super::map::{Map, Pos};
+use anyhow::{anyhow, Error};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const WHITE: Color = Color::new(255, 255, 255);
+    pub const GRAY: Color = Color::new(128, 128, 128);
+    pub const RED: Color = Color::new(220, 50, 47);
+    pub const GREEN: Color = Color::new(0, 170, 0);
+    pub const BLUE: Color = Color::new(38, 139, 210);
+    pub const YELLOW: Color = Color::new(240, 200, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Clone)]
+enum Overlay {
+    Highlight(Vec<Pos>, Color),
+    Path(Vec<Pos>, Color),
+}
+
+#[derive(Clone)]
+pub struct Image {
+    pub size: Pos,
+    pub scale: usize,
+    pub cells: Vec<Color>,
+    overlays: Vec<Overlay>,
+}
+
+impl Image {
+    pub fn from_map<T>(map: &Map<T>, color: impl Fn(&T) -> Color) -> Self {
+        Self {
+            size: map.size,
+            scale: 1,
+            cells: map.iter_pos().map(|(_, value)| color(value)).collect(),
+            overlays: vec![],
+        }
+    }
+
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn highlight(&mut self, positions: &[Pos], color: Color) {
+        self.overlays
+            .push(Overlay::Highlight(positions.to_vec(), color));
+    }
+
+    pub fn path(&mut self, positions: &[Pos], color: Color) {
+        self.overlays.push(Overlay::Path(positions.to_vec(), color));
+    }
+
+    pub fn width(&self) -> usize {
+        self.size.x as usize * self.scale
+    }
+
+    pub fn height(&self) -> usize {
+        self.size.y as usize * self.scale
+    }
+
+    fn contains(&self, pos: &Pos) -> bool {
+        0 <= pos.x && pos.x < self.size.x && 0 <= pos.y && pos.y < self.size.y
+    }
+
+    fn center(&self, pos: &Pos) -> (i64, i64) {
+        let scale = self.scale as i64;
+        (pos.x * scale + scale / 2, pos.y * scale + scale / 2)
+    }
+
+    pub fn pixels(&self) -> Vec<Color> {
+        let (width, scale) = (self.width(), self.scale);
+        let mut pixels = vec![Color::BLACK; width * self.height()];
+        let fill_cell = |pixels: &mut [Color], pos: &Pos, color: Color| {
+            for dy in 0..scale {
+                let row = (pos.y as usize * scale + dy) * width + pos.x as usize * scale;
+                pixels[row..row + scale].fill(color);
+            }
+        };
+        for (i, color) in self.cells.iter().enumerate() {
+            let pos = Pos {
+                x: i as i64 % self.size.x,
+                y: i as i64 / self.size.x,
+            };
+            fill_cell(&mut pixels, &pos, *color);
+        }
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Highlight(positions, color) => positions
+                    .iter()
+                    .filter(|pos| self.contains(pos))
+                    .for_each(|pos| fill_cell(&mut pixels, pos, *color)),
+                Overlay::Path(positions, color) => {
+                    for (from, to) in positions.iter().zip(positions.iter().skip(1)) {
+                        self.draw_line(&mut pixels, self.center(from), self.center(to), *color);
+                    }
+                    if let [single] = positions[..] {
+                        self.draw_line(
+                            &mut pixels,
+                            self.center(&single),
+                            self.center(&single),
+                            *color,
+                        );
+                    }
+                }
+            }
+        }
+        pixels
+    }
+
+    // Bresenham, clipped to the image bounds.
+    fn draw_line(&self, pixels: &mut [Color], from: (i64, i64), to: (i64, i64), color: Color) {
+        let (width, height) = (self.width() as i64, self.height() as i64);
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+        let mut err = dx + dy;
+        loop {
+            if 0 <= x && x < width && 0 <= y && y < height {
+                pixels[(y * width + x) as usize] = color;
+            }
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn rgb(&self) -> Vec<u8> {
+        self.pixels()
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect()
+    }
+
+    pub fn write_ppm<W: Write>(&self, f: &mut W) -> std::io::Result<()> {
+        writeln!(f, "P6\n{} {}\n255", self.width(), self.height())?;
+        f.write_all(&self.rgb())
+    }
+
+    pub fn write_png<W: Write>(&self, f: &mut W) -> std::io::Result<()> {
+        let (width, height) = (self.width(), self.height());
+        let rgb = self.rgb();
+        let raw: Vec<u8> = rgb
+            .chunks(3 * width)
+            .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+            .collect();
+
+        let mut header = vec![];
+        header.extend((width as u32).to_be_bytes());
+        header.extend((height as u32).to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+
+        f.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_png_chunk(f, b"IHDR", &header)?;
+        write_png_chunk(f, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(f, b"IEND", &[])
+    }
+
+    pub fn write_svg<W: Write>(&self, f: &mut W) -> std::io::Result<()> {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+            self.width(),
+            self.height(),
+            self.size.x,
+            self.size.y
+        )?;
+        for (y, row) in self.cells.chunks(self.size.x as usize).enumerate() {
+            let mut x = 0;
+            // Merge horizontal runs of equal color to keep the file small.
+            for (color, run) in run_lengths(row) {
+                writeln!(
+                    f,
+                    r#"<rect x="{}" y="{}" width="{}" height="1" fill="{}"/>"#,
+                    x,
+                    y,
+                    run,
+                    color.hex()
+                )?;
+                x += run;
+            }
+        }
+        for overlay in &self.overlays {
+            match overlay {
+                Overlay::Highlight(positions, color) => {
+                    for pos in positions.iter().filter(|pos| self.contains(pos)) {
+                        writeln!(
+                            f,
+                            r#"<rect x="{}" y="{}" width="1" height="1" fill="{}" fill-opacity="0.8"/>"#,
+                            pos.x,
+                            pos.y,
+                            color.hex()
+                        )?;
+                    }
+                }
+                Overlay::Path(positions, color) => {
+                    let points = positions
+                        .iter()
+                        .map(|pos| format!("{}.5,{}.5", pos.x, pos.y))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(
+                        f,
+                        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="0.3" stroke-linejoin="round" stroke-linecap="round"/>"#,
+                        points,
+                        color.hex()
+                    )?;
+                }
+            }
+        }
+        writeln!(f, "</svg>")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut f = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ppm") => self.write_ppm(&mut f)?,
+            Some("png") => self.write_png(&mut f)?,
+            Some("svg") => self.write_svg(&mut f)?,
+            _ => return Err(anyhow!("Unsupported image format: {}", path.display())),
+        }
+        f.flush()?;
+        Ok(())
+    }
+}
+
+fn run_lengths(row: &[Color]) -> Vec<(Color, usize)> {
+    let mut runs: Vec<(Color, usize)> = vec![];
+    for color in row {
+        match runs.last_mut() {
+            Some((last, run)) if last == color => *run += 1,
+            _ => runs.push((*color, 1)),
+        }
+    }
+    runs
+}
+
+fn write_png_chunk<W: Write>(f: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    f.write_all(&(data.len() as u32).to_be_bytes())?;
+    f.write_all(kind)?;
+    f.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data.iter()));
+    f.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    !bytes.fold(!0_u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1_u32, 0_u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+// A zlib stream made of uncompressed deflate blocks: larger files, but no compressor needed.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(if blocks.peek().is_none() { 1 } else { 0 });
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Undoes `zlib_stored`, checking the header, block lengths and checksum on the way.
+    fn inflate_stored(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(u16::from_be_bytes([stream[0], stream[1]]) % 31, 0);
+        let mut data = vec![];
+        let mut i = 2;
+        loop {
+            let last = stream[i] & 1 == 1;
+            assert_eq!(stream[i] >> 1, 0, "not a stored block");
+            let len = u16::from_le_bytes([stream[i + 1], stream[i + 2]]);
+            let nlen = u16::from_le_bytes([stream[i + 3], stream[i + 4]]);
+            assert_eq!(len, !nlen);
+            data.extend(&stream[i + 5..i + 5 + len as usize]);
+            i += 5 + len as usize;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(stream[i..], adler32(&data).to_be_bytes());
+        data
+    }
+
+    fn image() -> Image {
+        let map = Map {
+            size: Pos { x: 3, y: 2 },
+            data: vec![vec![0, 1, 1], vec![1, 0, 0]],
+        };
+        Image::from_map(&map, |&v| if v == 1 { Color::WHITE } else { Color::BLACK })
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789".iter()), 0xcbf43926);
+        assert_eq!(crc32(b"IEND".iter()), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn stored_deflate_round_trip() {
+        for len in [0, 1, 65535, 65536, 2 * 65535 + 10] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+            assert_eq!(inflate_stored(&zlib_stored(&data)), data);
+        }
+    }
+
+    #[test]
+    fn png_chunks() {
+        let mut png = vec![];
+        image().with_scale(2).write_png(&mut png).unwrap();
+        assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = vec![];
+        let mut i = 8;
+        while i < png.len() {
+            let len = u32::from_be_bytes(png[i..i + 4].try_into().unwrap()) as usize;
+            let body = &png[i + 4..i + 8 + len];
+            let crc = u32::from_be_bytes(png[i + 8 + len..i + 12 + len].try_into().unwrap());
+            assert_eq!(crc32(body.iter()), crc);
+            chunks.push((body[..4].to_vec(), body[4..].to_vec()));
+            i += 12 + len;
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| &kind[..]).collect();
+        assert_eq!(kinds, vec![&b"IHDR"[..], b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 6, 0, 0, 0, 4, 8, 2, 0, 0, 0]);
+
+        let raw = inflate_stored(&chunks[1].1);
+        assert_eq!(raw.len(), 4 * (1 + 6 * 3));
+        let (b, w) = ([0, 0, 0], [255, 255, 255]);
+        let first_row: Vec<u8> = [0].into_iter().chain([b, b, w, w, w, w].concat()).collect();
+        assert_eq!(raw[..19], first_row);
+    }
+
+    #[test]
+    fn overlays_and_other_formats() {
+        let mut image = image();
+        image.highlight(&[Pos { x: 2, y: 1 }, Pos { x: 9, y: 9 }], Color::RED);
+        let pixels = image.pixels();
+        assert_eq!(pixels[5], Color::RED);
+        assert_eq!(pixels[4], Color::BLACK);
+
+        let mut ppm = vec![];
+        image.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 18);
+
+        let mut svg = vec![];
+        image.write_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains(r##"<rect x="1" y="0" width="2" height="1" fill="#ffffff"/>"##));
+        assert_eq!(svg.matches("<rect").count(), 5);
+    }
+}
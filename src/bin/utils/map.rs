@@ -76,7 +76,18 @@ impl ConvertibleFromChar for u8 {
     }
 }
 
+pub trait ConvertibleToChar {
+    fn to_char(&self) -> char;
+}
+
+impl ConvertibleToChar for char {
+    fn to_char(&self) -> char {
+        *self
+    }
+}
+
 impl<T: 'static + ConvertibleFromChar + Copy> Map<T> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Result<Self, Error> {
         let data: Vec<Vec<T>> = s
             .lines()
@@ -86,13 +97,16 @@ impl<T: 'static + ConvertibleFromChar + Copy> Map<T> {
             x: data.first().ok_or(Error::msg("Empty data"))?.len() as i64,
             y: data.len() as i64,
         };
+        if data.iter().any(|row| row.len() as i64 != size.x) {
+            return Err(Error::msg("Rows have different lengths"));
+        }
         Ok(Self { data, size })
     }
 }
 
 impl<T> Map<T> {
     pub fn contains(&self, pos: &Pos) -> bool {
-        0 <= pos.x && pos.x < self.size.y && 0 <= pos.y && pos.y < self.size.y
+        0 <= pos.x && pos.x < self.size.x && 0 <= pos.y && pos.y < self.size.y
     }
 
     pub fn neighbors(&self, pos: &Pos) -> Vec<Pos> {
@@ -113,6 +127,55 @@ impl<T> Map<T> {
             .filter(|pos| self.contains(pos))
             .collect()
     }
+
+    pub fn iter_pos(&self) -> impl Iterator<Item = (Pos, &T)> + '_ {
+        self.data.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, value)| {
+                (
+                    Pos {
+                        x: x as i64,
+                        y: y as i64,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+
+    pub fn iter_pos_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> + '_ {
+        self.data.iter_mut().enumerate().flat_map(|(y, row)| {
+            row.iter_mut().enumerate().map(move |(x, value)| {
+                (
+                    Pos {
+                        x: x as i64,
+                        y: y as i64,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+
+    pub fn positions_where<'a>(
+        &'a self,
+        pred: impl Fn(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = Pos> + 'a {
+        self.iter_pos()
+            .filter(move |(_, value)| pred(value))
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn count(&self, pred: impl Fn(&T) -> bool) -> usize {
+        self.iter_pos().filter(|(_, value)| pred(value)).count()
+    }
+}
+
+impl<T: PartialEq> Map<T> {
+    pub fn find(&self, value: &T) -> Option<Pos> {
+        self.iter_pos()
+            .find(|(_, other)| *other == value)
+            .map(|(pos, _)| pos)
+    }
 }
 
 impl<T> Index<&Pos> for Map<T> {
@@ -137,3 +200,82 @@ impl<T: std::fmt::Debug> Display for Map<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wider than tall, so mixing up x and y shows.
+    fn wide() -> Map<char> {
+        Map::from_str("abcd\nefgh\n").unwrap()
+    }
+
+    #[test]
+    fn contains_edges() {
+        let map = wide();
+        assert_eq!(map.size, Pos { x: 4, y: 2 });
+        assert!(map.contains(&Pos { x: 0, y: 0 }));
+        assert!(map.contains(&Pos { x: 3, y: 1 }));
+        assert!(map.contains(&Pos { x: 2, y: 0 }));
+        assert!(!map.contains(&Pos { x: 4, y: 0 }));
+        assert!(!map.contains(&Pos { x: 0, y: 2 }));
+        assert!(!map.contains(&Pos { x: -1, y: 1 }));
+        assert!(!map.contains(&Pos { x: 1, y: -1 }));
+
+        let tall: Map<char> = Map::from_str("ab\ncd\nef\n").unwrap();
+        assert!(tall.contains(&Pos { x: 1, y: 2 }));
+        assert!(!tall.contains(&Pos { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn neighbors_stay_inside() {
+        let map = wide();
+        let sorted = |pos: Pos| {
+            let mut neighbors = map.neighbors(&pos);
+            neighbors.sort_by_key(|pos| (pos.y, pos.x));
+            neighbors
+        };
+        assert_eq!(
+            sorted(Pos { x: 3, y: 0 }),
+            vec![Pos { x: 2, y: 0 }, Pos { x: 3, y: 1 }]
+        );
+        assert_eq!(
+            sorted(Pos { x: 2, y: 1 }),
+            vec![Pos { x: 2, y: 0 }, Pos { x: 1, y: 1 }, Pos { x: 3, y: 1 }]
+        );
+    }
+
+    #[test]
+    fn position_helpers() {
+        let mut map = wide();
+        let positions: Vec<Pos> = map.iter_pos().map(|(pos, _)| pos).collect();
+        assert_eq!(positions.len(), 8);
+        assert_eq!(positions[5], Pos { x: 1, y: 1 });
+        assert!(map.iter_pos().all(|(pos, &c)| map[&pos] == c));
+
+        for (pos, c) in map.iter_pos_mut() {
+            if pos.x == 3 {
+                *c = 'z';
+            }
+        }
+        assert_eq!(
+            map.data,
+            vec![vec!['a', 'b', 'c', 'z'], vec!['e', 'f', 'g', 'z']]
+        );
+        assert_eq!(
+            map.positions_where(|&c| c == 'z').collect::<Vec<_>>(),
+            vec![Pos { x: 3, y: 0 }, Pos { x: 3, y: 1 }]
+        );
+        assert_eq!(map.count(|&c| c < 'c'), 2);
+        assert_eq!(map.find(&'g'), Some(Pos { x: 2, y: 1 }));
+        assert_eq!(map.find(&'d'), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Map::<char>::from_str("").is_err());
+        assert!(Map::<char>::from_str("abc\nab\n").is_err());
+        assert!(Map::<u8>::from_str("12\n3x\n").is_err());
+        assert_eq!(Map::<u8>::from_str("12\n34\n").unwrap().data[1], vec![3, 4]);
+    }
+}
@@ -0,0 +1,195 @@
+use super::map::ConvertibleFromChar;
+use super::search::Neighbors;
+use anyhow::Error;
+use itertools::Itertools;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Index, IndexMut, Mul};
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
+pub struct Pos3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Pos3 {
+    pub fn adjacent(&self) -> Vec<Pos3> {
+        [-1, 1]
+            .iter()
+            .flat_map(|&i| {
+                [
+                    Pos3 { x: i, y: 0, z: 0 },
+                    Pos3 { x: 0, y: i, z: 0 },
+                    Pos3 { x: 0, y: 0, z: i },
+                ]
+            })
+            .map(|delta| *self + delta)
+            .collect()
+    }
+
+    pub fn surrounding(&self) -> Vec<Pos3> {
+        (-1..=1)
+            .cartesian_product(-1..=1)
+            .cartesian_product(-1..=1)
+            .filter(|&((x, y), z)| (x, y, z) != (0, 0, 0))
+            .map(|((x, y), z)| *self + Pos3 { x, y, z })
+            .collect()
+    }
+
+    pub fn manhattan(&self, other: &Pos3) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+}
+
+impl Add for Pos3 {
+    type Output = Pos3;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Mul<i64> for Pos3 {
+    type Output = Pos3;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Map3<T> {
+    pub size: Pos3,
+    pub data: Vec<Vec<Vec<T>>>,
+}
+
+impl<T: 'static + ConvertibleFromChar + Copy> Map3<T> {
+    // Layers (increasing z) are separated by blank lines.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Result<Self, Error> {
+        let data: Vec<Vec<Vec<T>>> = s
+            .split("\n\n")
+            .filter(|layer| !layer.trim().is_empty())
+            .map(|layer| {
+                layer
+                    .lines()
+                    .map(|l| l.chars().map(T::from_char).collect())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let first_layer = data.first().ok_or(Error::msg("Empty data"))?;
+        let size = Pos3 {
+            x: first_layer.first().ok_or(Error::msg("Empty layer"))?.len() as i64,
+            y: first_layer.len() as i64,
+            z: data.len() as i64,
+        };
+        let layers_match = data.iter().all(|layer| {
+            layer.len() as i64 == size.y && layer.iter().all(|row| row.len() as i64 == size.x)
+        });
+        if size.x == 0 || !layers_match {
+            return Err(Error::msg("Layers or rows have different lengths"));
+        }
+        Ok(Self { data, size })
+    }
+}
+
+impl<T> Map3<T> {
+    pub fn contains(&self, pos: &Pos3) -> bool {
+        0 <= pos.x
+            && pos.x < self.size.x
+            && 0 <= pos.y
+            && pos.y < self.size.y
+            && 0 <= pos.z
+            && pos.z < self.size.z
+    }
+
+    pub fn neighbors(&self, pos: &Pos3) -> Vec<Pos3> {
+        pos.adjacent()
+            .into_iter()
+            .filter(|pos| self.contains(pos))
+            .collect()
+    }
+
+    pub fn neighbors_diagonal(&self, pos: &Pos3) -> Vec<Pos3> {
+        pos.surrounding()
+            .into_iter()
+            .filter(|pos| self.contains(pos))
+            .collect()
+    }
+}
+
+impl<T> Neighbors for Map3<T> {
+    type Pos = Pos3;
+
+    fn neighbors(&self, pos: &Pos3) -> Vec<Pos3> {
+        Map3::neighbors(self, pos)
+    }
+}
+
+impl<T> Index<&Pos3> for Map3<T> {
+    type Output = T;
+
+    fn index(&self, pos: &Pos3) -> &Self::Output {
+        &self.data[pos.z as usize][pos.y as usize][pos.x as usize]
+    }
+}
+
+impl<T> IndexMut<&Pos3> for Map3<T> {
+    fn index_mut(&mut self, pos: &Pos3) -> &mut Self::Output {
+        &mut self.data[pos.z as usize][pos.y as usize][pos.x as usize]
+    }
+}
+
+impl<T: std::fmt::Debug> Display for Map3<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for layer in self.data.iter() {
+            for line in layer.iter() {
+                writeln!(f, "{:?}", line)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let map: Map3<char> = Map3::from_str("ab\ncd\nef\n\ngh\nij\nkl\n").unwrap();
+        assert_eq!(map.size, Pos3 { x: 2, y: 3, z: 2 });
+        assert_eq!(map[&Pos3 { x: 1, y: 2, z: 1 }], 'l');
+        assert!(!map.contains(&Pos3 { x: 2, y: 0, z: 0 }));
+        assert!(Map3::<char>::from_str("\n\n").is_err());
+        assert!(Map3::<char>::from_str("ab\nc\n\nde\nfg\n").is_err());
+        assert!(Map3::<char>::from_str("ab\ncd\n\nef\n").is_err());
+        assert!(Map3::<char>::from_str("ab\ncd\n\nefg\nhij\n").is_err());
+    }
+
+    #[test]
+    fn neighborhoods() {
+        let map: Map3<char> =
+            Map3::from_str("...\n...\n...\n\n...\n...\n...\n\n...\n...\n...\n").unwrap();
+        let center = Pos3 { x: 1, y: 1, z: 1 };
+        let corner = Pos3 { x: 0, y: 0, z: 0 };
+        assert_eq!(map.neighbors(&center).len(), 6);
+        assert_eq!(map.neighbors_diagonal(&center).len(), 26);
+        assert_eq!(map.neighbors(&corner).len(), 3);
+        assert_eq!(map.neighbors_diagonal(&corner).len(), 7);
+        assert!(map
+            .neighbors(&center)
+            .iter()
+            .all(|pos| pos.manhattan(&center) == 1));
+        assert_eq!(corner.manhattan(&Pos3 { x: 2, y: -1, z: 2 }), 5);
+    }
+}
@@ -0,0 +1,292 @@
+use num_traits::{PrimInt, Signed};
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+pub fn gcd<T: PrimInt + Signed>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != T::zero() {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn lcm<T: PrimInt + Signed>(a: T, b: T) -> T {
+    if a == T::zero() || b == T::zero() {
+        T::zero()
+    } else {
+        (a / gcd(a, b) * b).abs()
+    }
+}
+
+// Returns (g, x, y) with a * x + b * y = g = gcd(a, b).
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(modulus), modulus);
+    (g == 1).then(|| x.rem_euclid(modulus))
+}
+
+// Solves x = r_i (mod m_i) for all (r_i, m_i); moduli need not be coprime.
+// Returns (x, lcm of the moduli), or None if the congruences are incompatible.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    congruences
+        .iter()
+        .try_fold((0_i64, 1_i64), |(r1, m1), &(r2, m2)| {
+            let (g, p, _) = extended_gcd(m1, m2);
+            if (r2 - r1) % g != 0 {
+                return None;
+            }
+            let lcm = m1 / g * m2;
+            let k = (r2 - r1) as i128 / g as i128 * p as i128 % (m2 / g) as i128;
+            let x = (r1 as i128 + m1 as i128 * k).rem_euclid(lcm as i128);
+            Some((x as i64, lcm))
+        })
+}
+
+pub fn num_digits(n: u64) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
+// Splits a number with an even number of digits into its two halves, e.g. 1234 -> (12, 34).
+pub fn split_digits(n: u64) -> Option<(u64, u64)> {
+    let digits = num_digits(n);
+    if digits.is_multiple_of(2) {
+        let mask = 10_u64.pow(digits / 2);
+        Some((n / mask, n % mask))
+    } else {
+        None
+    }
+}
+
+// Concatenates the decimal representations, e.g. (12, 345) -> 12345.
+pub fn concat(a: u64, b: u64) -> Option<u64> {
+    a.checked_mul(10_u64.checked_pow(num_digits(b))?)?
+        .checked_add(b)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fraction {
+    pub num: i128,
+    pub den: i128,
+}
+
+impl Fraction {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "zero denominator");
+        let g = gcd(num, den) * den.signum();
+        Self {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    pub fn integer(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+}
+
+impl Add for Fraction {
+    type Output = Fraction;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Fraction::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Fraction;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Neg for Fraction {
+    type Output = Fraction;
+
+    fn neg(self) -> Self::Output {
+        Fraction {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Fraction;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Fraction::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for Fraction {
+    type Output = Fraction;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Fraction::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl Display for Fraction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// Cramer's rule for a * [x, y] = b; None if the system is singular.
+pub fn solve_2x2(a: [[i64; 2]; 2], b: [i64; 2]) -> Option<[Fraction; 2]> {
+    let [[a11, a12], [a21, a22]] = a.map(|row| row.map(|v| v as i128));
+    let [b1, b2] = b.map(|v| v as i128);
+    let d = a11 * a22 - a12 * a21;
+    if d == 0 {
+        return None;
+    }
+    Some([
+        Fraction::new(b1 * a22 - a12 * b2, d),
+        Fraction::new(a11 * b2 - b1 * a21, d),
+    ])
+}
+
+// Exact Gaussian elimination for a square system a * x = b; None if it is singular.
+pub fn solve_linear(a: &[Vec<i64>], b: &[i64]) -> Option<Vec<Fraction>> {
+    let n = b.len();
+    let mut rows: Vec<Vec<Fraction>> = a
+        .iter()
+        .zip(b)
+        .map(|(row, &rhs)| {
+            assert_eq!(row.len(), n, "matrix needs to be square");
+            row.iter()
+                .chain([rhs].iter())
+                .map(|&v| Fraction::integer(v as i128))
+                .collect()
+        })
+        .collect();
+
+    for col in 0..n {
+        let pivot = (col..n).find(|&row| !rows[row][col].is_zero())?;
+        rows.swap(col, pivot);
+        let pivot_row = rows[col].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != col && !row[col].is_zero() {
+                let factor = row[col] / pivot_row[col];
+                for (value, pivot_value) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *value = *value - factor * *pivot_value;
+                }
+            }
+        }
+    }
+    Some((0..n).map(|i| rows[i][n] / rows[i][i]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divisors() {
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(gcd(0_i32, 0), 0);
+        assert_eq!(lcm(-4, 6), 12);
+        assert_eq!(lcm(0_i64, 5), 0);
+        for a in -20..=20 {
+            for b in -20..=20 {
+                let (g, x, y) = extended_gcd(a, b);
+                assert_eq!(g, gcd(a, b));
+                assert_eq!(a * x + b * y, g, "extended_gcd({}, {})", a, b);
+            }
+        }
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-3, 7), Some(2));
+        assert_eq!(mod_inverse(4, 6), None);
+    }
+
+    #[test]
+    fn chinese_remainders() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+        for m1 in 1..=12 {
+            for m2 in 1..=12 {
+                for r1 in 0..m1 {
+                    for r2 in 0..m2 {
+                        let modulus = lcm(m1, m2);
+                        let expected = (0..modulus)
+                            .find(|x| x % m1 == r1 && x % m2 == r2)
+                            .map(|x| (x, modulus));
+                        assert_eq!(crt(&[(r1, m1), (r2, m2)]), expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_digits() {
+        assert_eq!(num_digits(0), 1);
+        assert_eq!(num_digits(9), 1);
+        assert_eq!(num_digits(10), 2);
+        assert_eq!(num_digits(u64::MAX), 20);
+        assert_eq!(split_digits(1234), Some((12, 34)));
+        assert_eq!(split_digits(1000), Some((10, 0)));
+        assert_eq!(split_digits(123), None);
+        assert_eq!(concat(12, 345), Some(12345));
+        assert_eq!(concat(7, 0), Some(70));
+        assert_eq!(concat(u64::MAX / 10, 99), None);
+    }
+
+    #[test]
+    fn fractions_stay_reduced() {
+        assert_eq!(Fraction::new(6, -4), Fraction { num: -3, den: 2 });
+        assert_eq!(Fraction::new(0, -5), Fraction::integer(0));
+        let sum = Fraction::new(1, 6) + Fraction::new(1, 3);
+        assert_eq!(sum, Fraction::new(1, 2));
+        assert_eq!((sum * Fraction::integer(4)).to_integer(), Some(2));
+        assert_eq!(sum - sum, Fraction::integer(0));
+        assert_eq!(
+            Fraction::new(2, 3) / Fraction::new(-4, 9),
+            Fraction::new(-3, 2)
+        );
+        assert_eq!(Fraction::new(-3, 2).to_string(), "-3/2");
+        assert_eq!(Fraction::integer(5).to_string(), "5");
+    }
+
+    #[test]
+    fn linear_systems() {
+        assert_eq!(
+            solve_2x2([[94, 22], [34, 67]], [8400, 5400]),
+            Some([Fraction::integer(80), Fraction::integer(40)])
+        );
+        assert_eq!(
+            solve_2x2([[2, 0], [0, 3]], [1, 1]),
+            Some([Fraction::new(1, 2), Fraction::new(1, 3)])
+        );
+        assert_eq!(solve_2x2([[1, 2], [2, 4]], [3, 6]), None);
+
+        // Needs a row swap: the first pivot is zero.
+        let a = vec![vec![0, 1, 1], vec![2, 1, 0], vec![1, 0, 3]];
+        let solution = solve_linear(&a, &[5, 4, 10]).unwrap();
+        assert_eq!(solution, [1, 2, 3].map(Fraction::integer).to_vec());
+        assert_eq!(solve_linear(&[vec![1, 1], vec![2, 2]], &[1, 2]), None);
+    }
+}
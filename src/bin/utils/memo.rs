@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::rc::Rc;
+
+type Function<'a, K, V> = Rc<dyn Fn(&mut Memo<'a, K, V>, K) -> V + 'a>;
+
+// Caches the results of a (possibly recursive) function; recursive calls go through `memo.get`.
+pub struct Memo<'a, K, V> {
+    function: Function<'a, K, V>,
+    cache: HashMap<K, V>,
+    hits: usize,
+    misses: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+    pub entries: usize,
+}
+
+impl<'a, K: Eq + Hash + Clone, V: Clone> Memo<'a, K, V> {
+    pub fn new(function: impl Fn(&mut Memo<'a, K, V>, K) -> V + 'a) -> Self {
+        Self {
+            function: Rc::new(function),
+            cache: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: K) -> V {
+        if let Some(value) = self.cache.get(&key) {
+            self.hits += 1;
+            return value.clone();
+        }
+        self.misses += 1;
+        let function = Rc::clone(&self.function);
+        let value = function(self, key.clone());
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.cache.len(),
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let calls = self.hits + self.misses;
+        write!(
+            f,
+            "{} calls, {} hits, {} misses ({:.1}% hit rate), {} entries",
+            calls,
+            self.hits,
+            self.misses,
+            100.0 * self.hits as f64 / calls.max(1) as f64,
+            self.entries
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci<'a>() -> Memo<'a, u64, u64> {
+        Memo::new(|memo, n| {
+            if n < 2 {
+                n
+            } else {
+                memo.get(n - 1) + memo.get(n - 2)
+            }
+        })
+    }
+
+    #[test]
+    fn recursive_calls_are_cached() {
+        let mut memo = fibonacci();
+        assert_eq!(memo.get(90), 2880067194370816120);
+        // Every n in 0..=90 is computed once; each n >= 2 asks for n - 2 after n - 1 filled it in.
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 88,
+                misses: 91,
+                entries: 91
+            }
+        );
+        assert_eq!(memo.get(50), 12586269025);
+        assert_eq!(memo.stats().hits, 89);
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let calls = std::cell::Cell::new(0);
+        let mut memo = Memo::new(|_, n: u32| {
+            calls.set(calls.get() + 1);
+            n * n
+        });
+        assert_eq!(memo.get(3) + memo.get(3), 18);
+        assert_eq!(calls.get(), 1);
+        memo.clear();
+        assert_eq!(
+            memo.stats(),
+            Stats {
+                hits: 0,
+                misses: 0,
+                entries: 0
+            }
+        );
+        assert_eq!(memo.get(3), 9);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(
+            memo.stats().to_string(),
+            "1 calls, 0 hits, 1 misses (0.0% hit rate), 1 entries"
+        );
+    }
+}
@@ -1 +1,18 @@
+pub mod aho_corasick;
+pub mod animate;
+pub mod bitmap;
+pub mod counter;
+pub mod cycle;
+pub mod diff;
+pub mod hex;
+pub mod image;
 pub mod map;
+pub mod map3;
+pub mod math;
+pub mod memo;
+pub mod prop;
+pub mod rand;
+pub mod record;
+pub mod search;
+pub mod sparse;
+pub mod torus;
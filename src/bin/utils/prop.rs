@@ -0,0 +1,166 @@
+use super::rand::Rng;
+use std::fmt::Debug;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub cases: usize,
+    pub seed: u64,
+    pub max_shrinks: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cases: 500,
+            seed: std::env::var("PROP_SEED")
+                .ok()
+                .and_then(|seed| seed.parse().ok())
+                .unwrap_or(2024),
+            max_shrinks: 10_000,
+        }
+    }
+}
+
+pub fn check<T: Clone + Debug>(
+    generate: impl Fn(&mut Rng) -> T,
+    shrink: impl Fn(&T) -> Vec<T>,
+    property: impl Fn(&T) -> bool,
+) {
+    check_with(Config::default(), generate, shrink, property)
+}
+
+pub fn check_with<T: Clone + Debug>(
+    config: Config,
+    generate: impl Fn(&mut Rng) -> T,
+    shrink: impl Fn(&T) -> Vec<T>,
+    property: impl Fn(&T) -> bool,
+) {
+    let mut rng = Rng::new(config.seed);
+    for case in 0..config.cases {
+        let input = generate(&mut rng);
+        if property(&input) {
+            continue;
+        }
+        let minimal = minimize(input.clone(), &shrink, &property, config.max_shrinks);
+        panic!(
+            "property failed on case {} (seed {})\n  input:   {:?}\n  minimal: {:?}",
+            case, config.seed, input, minimal
+        );
+    }
+}
+
+// Greedily moves to the first smaller candidate that still fails, until none does.
+fn minimize<T: Clone>(
+    mut input: T,
+    shrink: impl Fn(&T) -> Vec<T>,
+    property: impl Fn(&T) -> bool,
+    max_shrinks: usize,
+) -> T {
+    for _ in 0..max_shrinks {
+        match shrink(&input)
+            .into_iter()
+            .find(|candidate| !property(candidate))
+        {
+            Some(candidate) => input = candidate,
+            None => break,
+        }
+    }
+    input
+}
+
+pub fn shrink_int(n: i64) -> Vec<i64> {
+    let mut candidates = vec![];
+    for candidate in [0, n / 2, n - n.signum()] {
+        if candidate.abs() < n.abs() && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+pub fn shrink_toward(n: i64, target: i64) -> Vec<i64> {
+    shrink_int(n - target)
+        .into_iter()
+        .map(|candidate| candidate + target)
+        .collect()
+}
+
+pub fn shrink_vec<T: Clone>(items: &[T], shrink_item: impl Fn(&T) -> Vec<T>) -> Vec<Vec<T>> {
+    let mut candidates = vec![];
+    if items.len() > 1 {
+        candidates.push(items[..items.len() / 2].to_vec());
+        candidates.push(items[items.len() / 2..].to_vec());
+    }
+    for i in 0..items.len() {
+        let mut candidate = items.to_vec();
+        candidate.remove(i);
+        candidates.push(candidate);
+    }
+    for (i, item) in items.iter().enumerate() {
+        for smaller in shrink_item(item) {
+            let mut candidate = items.to_vec();
+            candidate[i] = smaller;
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrink_candidates() {
+        assert_eq!(shrink_int(-9), vec![0, -4, -8]);
+        assert_eq!(shrink_int(1), vec![0]);
+        assert!(shrink_int(0).is_empty());
+        assert_eq!(shrink_toward(7, 1), vec![1, 4, 6]);
+        assert_eq!(
+            shrink_vec(&[3, 1], |&n| shrink_int(n)),
+            vec![
+                vec![3],
+                vec![1],
+                vec![1],
+                vec![3],
+                vec![0, 1],
+                vec![1, 1],
+                vec![2, 1],
+                vec![3, 0]
+            ]
+        );
+    }
+
+    #[test]
+    fn minimize_finds_smallest_failure() {
+        let below_ten = |n: &i64| *n < 10;
+        assert_eq!(minimize(1000, |&n| shrink_int(n), below_ten, 10_000), 10);
+        // Stops after `max_shrinks` steps even if smaller failures remain.
+        assert_eq!(minimize(1000, |&n| shrink_int(n), below_ten, 3), 125);
+
+        let at_most_one_large = |items: &Vec<i64>| items.iter().filter(|&&n| n > 3).count() < 2;
+        let minimal = minimize(
+            vec![1, 9, 2, 7, 4],
+            |items| shrink_vec(items, |&n| shrink_int(n)),
+            at_most_one_large,
+            10_000,
+        );
+        assert_eq!(minimal, vec![4, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "minimal: 10")]
+    fn check_reports_minimal_input() {
+        let config = Config {
+            cases: 100,
+            seed: 1,
+            max_shrinks: 10_000,
+        };
+        check_with(
+            config,
+            |rng| rng.range(0..=1000),
+            |&n| shrink_int(n),
+            |&n| n < 10,
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Error;
+use std::io::{stdout, BufWriter, Write};
+use std::ops::RangeInclusive;
+
+// SplitMix64: tiny, seedable and good enough for generating test inputs.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "empty range");
+        self.next_u64() % n
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (lo, hi) = range.into_inner();
+        assert!(lo <= hi, "empty range");
+        lo + self.below((hi - lo) as u64 + 1) as i64
+    }
+
+    pub fn chance(&mut self, p: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64 <= p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+// Handles `--generate SIZE [SEED]` by printing a generated puzzle input; returns whether it did.
+pub fn maybe_generate(generate: fn(&mut Rng, usize) -> String) -> Result<bool, Error> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--generate");
+    if args.next().is_none() {
+        return Ok(false);
+    }
+    let size = args
+        .next()
+        .ok_or(Error::msg("--generate needs a size"))?
+        .parse()?;
+    let seed = args
+        .next()
+        .map(|seed| seed.parse())
+        .transpose()?
+        .unwrap_or(0);
+
+    let mut out = BufWriter::new(stdout().lock());
+    out.write_all(generate(&mut Rng::new(seed), size).as_bytes())?;
+    out.flush()?;
+    Ok(true)
+}
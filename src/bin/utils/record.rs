@@ -0,0 +1,418 @@
+use super::image::{Color, Image};
+use anyhow::{anyhow, Error};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+enum Output {
+    Gif {
+        file: BufWriter<File>,
+        delay: u16,
+        size: Option<(usize, usize)>,
+    },
+    PngSequence(PathBuf),
+}
+
+pub struct Recorder {
+    output: Output,
+    frames: usize,
+}
+
+impl Recorder {
+    pub fn gif(path: &Path, delay: u16) -> Result<Self, Error> {
+        Ok(Self {
+            output: Output::Gif {
+                file: BufWriter::new(File::create(path)?),
+                delay,
+                size: None,
+            },
+            frames: 0,
+        })
+    }
+
+    pub fn png_sequence(dir: &Path) -> Result<Self, Error> {
+        create_dir_all(dir)?;
+        Ok(Self {
+            output: Output::PngSequence(dir.to_path_buf()),
+            frames: 0,
+        })
+    }
+
+    pub fn from_path(path: &Path, delay: u16) -> Result<Self, Error> {
+        match path.extension() {
+            Some(ext) if ext == "gif" => Self::gif(path, delay),
+            Some(_) => Err(anyhow!("Unsupported recording format: {}", path.display())),
+            None => Self::png_sequence(path),
+        }
+    }
+
+    pub fn push(&mut self, image: &Image) -> Result<(), Error> {
+        match &mut self.output {
+            Output::Gif { file, delay, size } => {
+                let frame_size = (image.width(), image.height());
+                if let Some(size) = size {
+                    if *size != frame_size {
+                        return Err(anyhow!("All GIF frames need to have the same size"));
+                    }
+                } else {
+                    write_gif_header(file, frame_size)?;
+                    *size = Some(frame_size);
+                }
+                write_gif_frame(file, image, *delay)?;
+            }
+            Output::PngSequence(dir) => {
+                let path = dir.join(format!("{:06}.png", self.frames));
+                image.write_png(&mut BufWriter::new(File::create(path)?))?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<usize, Error> {
+        if let Output::Gif { mut file, size, .. } = self.output {
+            if size.is_some() {
+                file.write_all(&[0x3b])?;
+            }
+            file.flush()?;
+        }
+        Ok(self.frames)
+    }
+}
+
+fn write_gif_header<W: Write>(f: &mut W, (width, height): (usize, usize)) -> std::io::Result<()> {
+    f.write_all(b"GIF89a")?;
+    f.write_all(&(width as u16).to_le_bytes())?;
+    f.write_all(&(height as u16).to_le_bytes())?;
+    // No global color table: every frame carries its own palette.
+    f.write_all(&[0, 0, 0])?;
+    // Loop forever.
+    f.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")
+}
+
+fn palette(pixels: &[Color]) -> (Vec<Color>, Vec<u8>) {
+    let mut colors: HashMap<Color, u8> = HashMap::new();
+    let mut table = vec![];
+    let mut indices = Vec::with_capacity(pixels.len());
+    for (i, pixel) in pixels.iter().enumerate() {
+        if i > 0 && pixels[i - 1] == *pixel {
+            indices.push(indices[i - 1]);
+        } else if let Some(&index) = colors.get(pixel) {
+            indices.push(index);
+        } else if table.len() < 256 {
+            colors.insert(*pixel, table.len() as u8);
+            indices.push(table.len() as u8);
+            table.push(*pixel);
+        } else {
+            return quantized_palette(pixels);
+        }
+    }
+    (table, indices)
+}
+
+// Fallback for frames with more than 256 colors: a fixed 3-3-2 bit RGB palette.
+fn quantized_palette(pixels: &[Color]) -> (Vec<Color>, Vec<u8>) {
+    let table = (0..=255_u8)
+        .map(|i| Color::new((i >> 5) * 36, ((i >> 2) & 7) * 36, (i & 3) * 85))
+        .collect();
+    let indices = pixels
+        .iter()
+        .map(|c| ((c.r / 32) << 5) | ((c.g / 32) << 2) | (c.b / 64))
+        .collect();
+    (table, indices)
+}
+
+fn write_gif_frame<W: Write>(f: &mut W, image: &Image, delay: u16) -> std::io::Result<()> {
+    let (table, indices) = palette(&image.pixels());
+    let table_bits = (table.len().max(2) as u32)
+        .next_power_of_two()
+        .trailing_zeros();
+
+    f.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+    f.write_all(&delay.to_le_bytes())?;
+    f.write_all(&[0x00, 0x00])?;
+
+    f.write_all(&[0x2c, 0, 0, 0, 0])?;
+    f.write_all(&(image.width() as u16).to_le_bytes())?;
+    f.write_all(&(image.height() as u16).to_le_bytes())?;
+    f.write_all(&[0x80 | (table_bits - 1) as u8])?;
+    for i in 0..1 << table_bits {
+        let color = table.get(i).unwrap_or(&Color::BLACK);
+        f.write_all(&[color.r, color.g, color.b])?;
+    }
+
+    let min_code_size = table_bits.max(2);
+    f.write_all(&[min_code_size as u8])?;
+    for block in lzw_encode(&indices, min_code_size, 1 << table_bits).chunks(255) {
+        f.write_all(&[block.len() as u8])?;
+        f.write_all(block)?;
+    }
+    f.write_all(&[0])
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn lzw_encode(indices: &[u8], min_code_size: u32, num_colors: usize) -> Vec<u8> {
+    const MAX_CODE: u16 = 4095;
+    let clear = 1_u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter {
+        bytes: vec![],
+        buffer: 0,
+        bits: 0,
+    };
+    // Flat (code, index) -> code lookup; 0 marks a missing entry, as no new code can be 0.
+    let mut dictionary = vec![0_u16; (MAX_CODE as usize + 1) * num_colors];
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end + 1;
+    out.write(clear, code_size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end, code_size);
+        return out.finish();
+    };
+    let mut current = first as u16;
+    for &index in rest {
+        let entry = current as usize * num_colors + index as usize;
+        if dictionary[entry] != 0 {
+            current = dictionary[entry];
+            continue;
+        }
+        out.write(current, code_size);
+        if next_code <= MAX_CODE {
+            dictionary[entry] = next_code;
+            next_code += 1;
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            out.write(clear, code_size);
+            dictionary.fill(0);
+            code_size = min_code_size + 1;
+            next_code = end + 1;
+        }
+        current = index as u16;
+    }
+    out.write(current, code_size);
+    if next_code == 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    out.write(end, code_size);
+    out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::map::{Map, Pos};
+    use super::super::rand::Rng;
+    use super::*;
+
+    fn image(width: i64, height: i64, color: impl Fn(i64, i64) -> Color) -> Image {
+        let map = Map {
+            size: Pos {
+                x: width,
+                y: height,
+            },
+            data: (0..height)
+                .map(|y| (0..width).map(|x| color(x, y)).collect())
+                .collect(),
+        };
+        Image::from_map(&map, |&c| c)
+    }
+
+    struct Decoded {
+        indices: Vec<u8>,
+        clears: usize,
+        max_code_size: u32,
+    }
+
+    // A plain GIF LZW decoder, following the spec rather than the encoder.
+    fn lzw_decode(bytes: &[u8], min_code_size: u32) -> Decoded {
+        let clear = 1_usize << min_code_size;
+        let end = clear + 1;
+        let mut decoded = Decoded {
+            indices: vec![],
+            clears: 0,
+            max_code_size: 0,
+        };
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<usize> = None;
+        let (mut buffer, mut bits, mut bytes) = (0_u32, 0, bytes.iter());
+        loop {
+            while bits < code_size {
+                buffer |= (*bytes.next().expect("missing end code") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            bits -= code_size;
+            decoded.max_code_size = decoded.max_code_size.max(code_size);
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.extend([vec![], vec![]]);
+                code_size = min_code_size + 1;
+                previous = None;
+                decoded.clears += 1;
+                continue;
+            }
+            if code == end {
+                assert!(bytes.all(|&b| b == 0), "data after end code");
+                return decoded;
+            }
+            let entry = match previous {
+                None => table[code].clone(),
+                Some(previous) => {
+                    let entry = if code < table.len() {
+                        table[code].clone()
+                    } else {
+                        assert_eq!(code, table.len(), "code not in table yet");
+                        let mut entry = table[previous].clone();
+                        entry.push(entry[0]);
+                        entry
+                    };
+                    if table.len() < 4096 {
+                        let mut new = table[previous].clone();
+                        new.push(entry[0]);
+                        table.push(new);
+                        if table.len() == 1 << code_size && code_size < 12 {
+                            code_size += 1;
+                        }
+                    }
+                    entry
+                }
+            };
+            decoded.indices.extend(&entry);
+            previous = Some(code);
+        }
+    }
+
+    // Reads one image block starting at `data[0]`, returning its pixels and the bytes consumed.
+    fn read_frame(data: &[u8], width: usize, height: usize) -> (Vec<Color>, Decoded, usize) {
+        assert_eq!(data[..4], [0x21, 0xf9, 0x04, 0x00]);
+        assert_eq!(data[8], 0x2c);
+        let mut i = 9 + 4;
+        assert_eq!(u16::from_le_bytes([data[i], data[i + 1]]) as usize, width);
+        assert_eq!(
+            u16::from_le_bytes([data[i + 2], data[i + 3]]) as usize,
+            height
+        );
+        let table_bits = (data[i + 4] & 7) as u32 + 1;
+        i += 5;
+        let table: Vec<Color> = data[i..i + (3 << table_bits)]
+            .chunks(3)
+            .map(|c| Color::new(c[0], c[1], c[2]))
+            .collect();
+        i += 3 << table_bits;
+        let min_code_size = data[i] as u32;
+        i += 1;
+        let mut lzw = vec![];
+        while data[i] != 0 {
+            let len = data[i] as usize;
+            lzw.extend(&data[i + 1..i + 1 + len]);
+            i += 1 + len;
+        }
+        let decoded = lzw_decode(&lzw, min_code_size);
+        let pixels = decoded.indices.iter().map(|&j| table[j as usize]).collect();
+        (pixels, decoded, i + 1)
+    }
+
+    fn round_trip(image: &Image) -> Decoded {
+        let mut gif = vec![];
+        write_gif_frame(&mut gif, image, 7).unwrap();
+        assert_eq!(gif[4..6], 7_u16.to_le_bytes());
+        let (pixels, decoded, len) = read_frame(&gif, image.width(), image.height());
+        assert_eq!(len, gif.len());
+        assert_eq!(pixels, image.pixels());
+        decoded
+    }
+
+    #[test]
+    fn small_frames() {
+        round_trip(&image(1, 1, |_, _| Color::RED));
+        round_trip(&image(5, 3, |x, y| {
+            if (x + y) % 2 == 0 {
+                Color::WHITE
+            } else {
+                Color::BLUE
+            }
+        }));
+        // Long runs of one color exercise the code-not-yet-in-table case.
+        round_trip(&image(40, 40, |_, _| Color::GREEN).with_scale(3));
+    }
+
+    #[test]
+    fn code_size_growth_and_dictionary_reset() {
+        let mut rng = Rng::new(33);
+        let colors: Vec<Color> = (0..16)
+            .map(|i| Color::new(i * 16, 0, 255 - i * 16))
+            .collect();
+        let cells: Vec<Color> = (0..120 * 100).map(|_| *rng.choose(&colors)).collect();
+        let decoded = round_trip(&image(120, 100, |x, y| cells[(y * 120 + x) as usize]));
+        assert_eq!(decoded.max_code_size, 12);
+        assert!(decoded.clears > 1, "dictionary was never reset");
+    }
+
+    #[test]
+    fn too_many_colors_are_quantized() {
+        let image = image(30, 30, |x, y| Color::new(x as u8 * 8, y as u8 * 8, 100));
+        let mut gif = vec![];
+        write_gif_frame(&mut gif, &image, 0).unwrap();
+        let (pixels, _, _) = read_frame(&gif, 30, 30);
+        for (pixel, original) in pixels.iter().zip(image.pixels()) {
+            assert_eq!(pixel.r, original.r / 32 * 36);
+            assert_eq!(pixel.g, original.g / 32 * 36);
+            assert_eq!(pixel.b, original.b / 64 * 85);
+        }
+    }
+
+    #[test]
+    fn recorded_file() {
+        let path = std::env::temp_dir().join(format!("record-test-{}.gif", std::process::id()));
+        let frames = [Color::RED, Color::YELLOW, Color::BLUE].map(|c| image(4, 2, move |_, _| c));
+        let mut recorder = Recorder::from_path(&path, 10).unwrap();
+        for frame in &frames {
+            recorder.push(frame).unwrap();
+        }
+        assert!(recorder.push(&image(2, 2, |_, _| Color::RED)).is_err());
+        assert_eq!(recorder.finish().unwrap(), 3);
+
+        let gif = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(gif[..10], *b"GIF89a\x04\x00\x02\x00");
+        let mut i = 13 + 19;
+        for frame in &frames {
+            let (pixels, _, len) = read_frame(&gif[i..], 4, 2);
+            assert_eq!(pixels, frame.pixels());
+            i += len;
+        }
+        assert_eq!(gif[i..], [0x3b]);
+        assert!(Recorder::from_path(Path::new("out.bmp"), 1).is_err());
+    }
+}
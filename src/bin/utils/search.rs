@@ -0,0 +1,116 @@
+use super::map::{Map, Pos};
+use super::torus::TorusMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+pub trait Neighbors {
+    type Pos: Copy + Eq + Hash;
+
+    fn neighbors(&self, pos: &Self::Pos) -> Vec<Self::Pos>;
+}
+
+impl<T> Neighbors for Map<T> {
+    type Pos = Pos;
+
+    fn neighbors(&self, pos: &Pos) -> Vec<Pos> {
+        Map::neighbors(self, pos)
+    }
+}
+
+impl<T> Neighbors for TorusMap<T> {
+    type Pos = Pos;
+
+    fn neighbors(&self, pos: &Pos) -> Vec<Pos> {
+        TorusMap::neighbors(self, pos)
+    }
+}
+
+pub fn bfs<G: Neighbors>(
+    grid: &G,
+    start: G::Pos,
+    passable: impl Fn(&G::Pos, &G::Pos) -> bool,
+) -> HashMap<G::Pos, usize> {
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        let distance = distances[&current];
+        for neighbor in grid.neighbors(&current) {
+            if !distances.contains_key(&neighbor) && passable(&current, &neighbor) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    distances
+}
+
+pub fn flood_fill<G: Neighbors>(
+    grid: &G,
+    start: G::Pos,
+    connected: impl Fn(&G::Pos, &G::Pos) -> bool,
+) -> HashSet<G::Pos> {
+    let mut region = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(current) = stack.pop() {
+        for neighbor in grid.neighbors(&current) {
+            if !region.contains(&neighbor) && connected(&current, &neighbor) {
+                region.insert(neighbor);
+                stack.push(neighbor);
+            }
+        }
+    }
+    region
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::hex::{Hex, HexMap};
+    use super::super::map3::{Map3, Pos3};
+    use super::*;
+
+    #[test]
+    fn bfs_covers_wide_map() {
+        let map: Map<char> = Map::from_str("...\n...\n").unwrap();
+        let distances = bfs(&map, Pos { x: 0, y: 0 }, |_, _| true);
+        assert_eq!(distances.len(), 6);
+        assert_eq!(distances[&Pos { x: 2, y: 1 }], 3);
+    }
+
+    #[test]
+    fn bfs_respects_walls() {
+        let map: Map<char> = Map::from_str("..#.\n#...\n..#.\n").unwrap();
+        let distances = bfs(&map, Pos { x: 0, y: 0 }, |_, to| map[to] != '#');
+        assert_eq!(distances.len(), 9);
+        assert_eq!(distances[&Pos { x: 3, y: 0 }], 5);
+        assert_eq!(distances[&Pos { x: 0, y: 2 }], 4);
+        assert!(!distances.contains_key(&Pos { x: 2, y: 0 }));
+
+        let torus = TorusMap::from(map.clone());
+        let distances = bfs(&torus, Pos { x: 0, y: 0 }, |_, to| torus[to] != '#');
+        assert_eq!(distances[&Pos { x: 3, y: 0 }], 1);
+        assert_eq!(distances[&Pos { x: 3, y: 2 }], 2);
+    }
+
+    #[test]
+    fn flood_fill_regions() {
+        let map: Map<char> = Map::from_str("aab\nabb\nbba\n").unwrap();
+        let region = |start: Pos| flood_fill(&map, start, |from, to| map[from] == map[to]);
+        assert_eq!(region(Pos { x: 0, y: 0 }).len(), 3);
+        assert_eq!(region(Pos { x: 2, y: 0 }).len(), 5);
+        assert_eq!(region(Pos { x: 2, y: 2 }).len(), 1);
+    }
+
+    #[test]
+    fn other_grids() {
+        let hex: HexMap<char> = HexMap::from_str("...\n...\n...\n").unwrap();
+        let distances = bfs(&hex, Hex { q: 0, r: 0 }, |_, _| true);
+        assert_eq!(distances.len(), 9);
+        assert_eq!(distances[&Hex { q: 2, r: 2 }], 4);
+        assert_eq!(distances[&Hex { q: 2, r: 0 }], 2);
+
+        let cube: Map3<char> = Map3::from_str("..\n..\n\n..\n..\n").unwrap();
+        let distances = bfs(&cube, Pos3 { x: 0, y: 0, z: 0 }, |_, _| true);
+        assert_eq!(distances.len(), 8);
+        assert_eq!(distances[&Pos3 { x: 1, y: 1, z: 1 }], 3);
+    }
+}
@@ -0,0 +1,144 @@
+use super::map::Pos;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+#[derive(Clone)]
+pub struct SparseMap<T> {
+    pub default: T,
+    pub data: HashMap<Pos, T>,
+}
+
+impl<T> SparseMap<T> {
+    pub fn new(default: T) -> Self {
+        Self {
+            default,
+            data: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, pos: &Pos) -> bool {
+        self.data.contains_key(pos)
+    }
+
+    pub fn insert(&mut self, pos: Pos, value: T) -> Option<T> {
+        self.data.insert(pos, value)
+    }
+
+    pub fn remove(&mut self, pos: &Pos) -> Option<T> {
+        self.data.remove(pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        let mut positions = self.data.keys();
+        let first = *positions.next()?;
+        Some(positions.fold((first, first), |(min, max), pos| {
+            (
+                Pos {
+                    x: min.x.min(pos.x),
+                    y: min.y.min(pos.y),
+                },
+                Pos {
+                    x: max.x.max(pos.x),
+                    y: max.y.max(pos.y),
+                },
+            )
+        }))
+    }
+
+    pub fn rows(&self) -> Vec<Vec<&T>> {
+        let Some((min, max)) = self.bounds() else {
+            return vec![];
+        };
+        (min.y..=max.y)
+            .map(|y| (min.x..=max.x).map(|x| &self[&Pos { x, y }]).collect())
+            .collect()
+    }
+
+    pub fn render(&self, to_char: impl Fn(&T) -> char) -> String {
+        self.rows()
+            .iter()
+            .map(|row| row.iter().map(|value| to_char(value)).collect::<String>() + "\n")
+            .collect()
+    }
+}
+
+impl<T: Default> FromIterator<(Pos, T)> for SparseMap<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos, T)>>(iter: I) -> Self {
+        Self {
+            default: T::default(),
+            data: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Index<&Pos> for SparseMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: &Pos) -> &Self::Output {
+        self.data.get(pos).unwrap_or(&self.default)
+    }
+}
+
+impl<T: Clone> IndexMut<&Pos> for SparseMap<T> {
+    fn index_mut(&mut self, pos: &Pos) -> &mut Self::Output {
+        self.data
+            .entry(*pos)
+            .or_insert_with(|| self.default.clone())
+    }
+}
+
+impl<T: std::fmt::Debug> Display for SparseMap<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.rows() {
+            writeln!(f, "{:?}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_for_missing_cells() {
+        let mut map = SparseMap::new('.');
+        assert!(map.is_empty());
+        assert_eq!(map.bounds(), None);
+        assert_eq!(map[&Pos { x: 5, y: -5 }], '.');
+
+        map[&Pos { x: -1, y: 2 }] = '#';
+        assert!(map.contains(&Pos { x: -1, y: 2 }));
+        assert_eq!(map.insert(Pos { x: -1, y: 2 }, 'O'), Some('#'));
+        assert_eq!(map.remove(&Pos { x: -1, y: 2 }), Some('O'));
+        assert_eq!(map.len(), 0);
+    }
+
+    #[test]
+    fn bounds_and_render() {
+        let map: SparseMap<char> = [
+            (Pos { x: -1, y: 0 }, 'a'),
+            (Pos { x: 2, y: 1 }, 'b'),
+            (Pos { x: 0, y: -1 }, 'c'),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            map.bounds(),
+            Some((Pos { x: -1, y: -1 }, Pos { x: 2, y: 1 }))
+        );
+        assert_eq!(
+            map.render(|&c| if c == '\0' { '.' } else { c }),
+            ".c..\na...\n...b\n"
+        );
+    }
+}
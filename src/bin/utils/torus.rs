@@ -0,0 +1,146 @@
+use super::map::{Map, Pos};
+use std::fmt::{Display, Formatter};
+use std::ops::{Index, IndexMut};
+
+#[derive(Clone)]
+pub struct TorusMap<T> {
+    pub size: Pos,
+    pub data: Vec<Vec<T>>,
+}
+
+impl<T: Clone> TorusMap<T> {
+    pub fn new(size: Pos, value: T) -> Self {
+        Self {
+            size,
+            data: vec![vec![value; size.x as usize]; size.y as usize],
+        }
+    }
+}
+
+impl<T> TorusMap<T> {
+    pub fn wrap(&self, pos: &Pos) -> Pos {
+        *pos % self.size
+    }
+
+    // On a side shorter than 3 both steps along it wrap to the same cell, which is listed once.
+    pub fn neighbors(&self, pos: &Pos) -> Vec<Pos> {
+        let mut neighbors = Vec::with_capacity(4);
+        for delta in [-1, 1]
+            .iter()
+            .flat_map(|i| [Pos { x: *i, y: 0 }, Pos { x: 0, y: *i }])
+        {
+            let neighbor = self.wrap(&(*pos + delta));
+            if !neighbors.contains(&neighbor) {
+                neighbors.push(neighbor);
+            }
+        }
+        neighbors
+    }
+
+    pub fn walk(&self, start: &Pos, step: Pos) -> impl Iterator<Item = Pos> {
+        let start = *start;
+        let size = self.size;
+        (0..).map(move |i| (start + step * i) % size)
+    }
+}
+
+impl<T> From<Map<T>> for TorusMap<T> {
+    fn from(map: Map<T>) -> Self {
+        Self {
+            size: map.size,
+            data: map.data,
+        }
+    }
+}
+
+impl<T> From<TorusMap<T>> for Map<T> {
+    fn from(map: TorusMap<T>) -> Self {
+        Self {
+            size: map.size,
+            data: map.data,
+        }
+    }
+}
+
+impl<T> Index<&Pos> for TorusMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: &Pos) -> &Self::Output {
+        let pos = self.wrap(pos);
+        &self.data[pos.y as usize][pos.x as usize]
+    }
+}
+
+impl<T> IndexMut<&Pos> for TorusMap<T> {
+    fn index_mut(&mut self, pos: &Pos) -> &mut Self::Output {
+        let pos = self.wrap(pos);
+        &mut self.data[pos.y as usize][pos.x as usize]
+    }
+}
+
+impl<T: std::fmt::Debug> Display for TorusMap<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in self.data.iter() {
+            writeln!(f, "{:?}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping() {
+        let mut map = TorusMap::new(Pos { x: 3, y: 2 }, 0);
+        assert_eq!(map.wrap(&Pos { x: -1, y: 5 }), Pos { x: 2, y: 1 });
+        map[&Pos { x: -1, y: -1 }] = 7;
+        assert_eq!(map.data[1][2], 7);
+        assert_eq!(map[&Pos { x: 5, y: 3 }], 7);
+        assert_eq!(
+            map.walk(&Pos { x: 0, y: 0 }, Pos { x: 2, y: 1 })
+                .take(4)
+                .collect::<Vec<_>>(),
+            vec![
+                Pos { x: 0, y: 0 },
+                Pos { x: 2, y: 1 },
+                Pos { x: 1, y: 0 },
+                Pos { x: 0, y: 1 }
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbors_are_unique() {
+        let map = TorusMap::new(Pos { x: 4, y: 4 }, ());
+        let mut neighbors = map.neighbors(&Pos { x: 0, y: 3 });
+        neighbors.sort_by_key(|pos| (pos.y, pos.x));
+        assert_eq!(
+            neighbors,
+            vec![
+                Pos { x: 0, y: 0 },
+                Pos { x: 0, y: 2 },
+                Pos { x: 1, y: 3 },
+                Pos { x: 3, y: 3 }
+            ]
+        );
+
+        let narrow = TorusMap::new(Pos { x: 2, y: 1 }, ());
+        assert_eq!(
+            narrow.neighbors(&Pos { x: 0, y: 0 }),
+            vec![Pos { x: 1, y: 0 }, Pos { x: 0, y: 0 }]
+        );
+    }
+
+    #[test]
+    fn map_round_trip() {
+        let map = Map {
+            size: Pos { x: 2, y: 1 },
+            data: vec![vec!['a', 'b']],
+        };
+        let torus = TorusMap::from(map);
+        assert_eq!(torus[&Pos { x: 3, y: 0 }], 'b');
+        assert_eq!(Map::from(torus).data, vec![vec!['a', 'b']]);
+    }
+}